@@ -6,10 +6,7 @@ use std::{
     iter::Sum,
 };
 
-#[inline]
-fn zero<T: Sum>() -> T {
-    std::iter::empty().sum()
-}
+use crate::graph::{zero, SearchResult};
 
 #[repr(transparent)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -97,6 +94,7 @@ where
     }
 }
 
+#[allow(dead_code)]
 pub fn a_star<
     S: Hash + Eq + Clone + Debug,
     Ss: IntoIterator<Item = S>,
@@ -110,7 +108,9 @@ pub fn a_star<
     get_neighbors: N,
     heuristic: H,
     is_complete: C,
-) -> Option<Vec<S>> {
+) -> Option<SearchResult<S, W>> {
+    let mut expanded = 0;
+    let mut pushed = 0;
     let mut prev_state = HashMap::<S, S>::default();
     let mut discovered_distance = HashMap::<S, W>::default();
     let mut states_to_visit = BinaryHeap::<Reverse<AStarState<S, W>>>::default();
@@ -122,9 +122,16 @@ pub fn a_star<
             },
             state: initial_state.clone(),
         }));
+        pushed += 1;
         discovered_distance.insert(initial_state, zero());
     }
     while let Some(current_state) = states_to_visit.pop() {
+        let stale = discovered_distance
+            .get(&current_state.0.state)
+            .is_some_and(|best| best < &current_state.0.distance.discovered_distance);
+        if stale {
+            continue;
+        }
         if is_complete(&current_state.0.state) {
             let mut path = vec![current_state.0.state.clone()];
             let mut path_curr = current_state.0.state;
@@ -134,8 +141,14 @@ pub fn a_star<
                 path_curr = prev.clone()
             }
             path.reverse();
-            return Some(path);
+            return Some(SearchResult {
+                cost: current_state.0.distance.discovered_distance,
+                path,
+                expanded,
+                pushed,
+            });
         }
+        expanded += 1;
         for (neighbor, distance) in get_neighbors(current_state.0.state.clone()) {
             // println!("The distance from {:?} to neighbor {neighbor:?} would be {distance:?}",current_state.0.state);
             let tentative_discovered_distance: W = [
@@ -159,32 +172,310 @@ pub fn a_star<
                 }
             };
             if new_shortest {
-                let mut neighbor_in_heap = false;
-                // Alter the discovered distances in the heap
-                states_to_visit = states_to_visit
-                    .into_vec()
-                    .into_iter()
-                    .map(|mut s| {
-                        if s.0.state == neighbor {
-                            neighbor_in_heap = true;
-                            s.0.distance.discovered_distance =
-                                tentative_discovered_distance.clone();
-                        }
-                        s
-                    })
-                    .collect();
-                if !neighbor_in_heap {
-                    states_to_visit.push(Reverse(AStarState {
-                        distance: AStarDisance {
-                            discovered_distance: tentative_discovered_distance,
-                            heuristic: heuristic(&neighbor),
-                        },
-                        state: neighbor.clone(),
-                    }));
-                }
+                // Any older entry for the neighbor stays in the heap and is skipped once popped
+                states_to_visit.push(Reverse(AStarState {
+                    distance: AStarDisance {
+                        discovered_distance: tentative_discovered_distance,
+                        heuristic: heuristic(&neighbor),
+                    },
+                    state: neighbor.clone(),
+                }));
+                pushed += 1;
                 prev_state.insert(neighbor, current_state.0.state.clone());
             }
         }
     }
     None
 }
+
+/// Runs [`a_star`] backwards from the goal states, where `get_predecessors` gives the states that can step to a state
+/// along with the cost of that step. The returned path still runs from a start state to a goal.
+#[allow(dead_code)]
+pub fn reverse_a_star<
+    S: Hash + Eq + Clone + Debug,
+    Ss: IntoIterator<Item = S>,
+    W: Sum + Clone + Ord + Debug,
+    P: Fn(S) -> Ps,
+    Ps: IntoIterator<Item = (S, W)>,
+    H: Fn(&S) -> W,
+    C: Fn(&S) -> bool,
+>(
+    goal_states: Ss,
+    get_predecessors: P,
+    heuristic: H,
+    is_start: C,
+) -> Option<SearchResult<S, W>> {
+    let mut result = a_star(goal_states, get_predecessors, heuristic, is_start)?;
+    result.path.reverse();
+    Some(result)
+}
+
+/// Iterative-deepening A*: repeated depth-first searches bounded by an increasing estimate,
+/// so memory only grows with the length of the current path rather than the number of states seen.
+///
+/// Only states on the current path are checked for cycles, so graphs with many routes to each state get re-explored.
+#[allow(dead_code)]
+pub fn ida_star<
+    S: Eq + Clone,
+    Ss: IntoIterator<Item = S>,
+    W: Sum + Clone + Ord,
+    N: Fn(S) -> Ns,
+    Ns: IntoIterator<Item = (S, W)>,
+    H: Fn(&S) -> W,
+    C: Fn(&S) -> bool,
+>(
+    initial_states: Ss,
+    get_neighbors: N,
+    heuristic: H,
+    is_complete: C,
+) -> Option<SearchResult<S, W>> {
+    let initial_states: Vec<S> = initial_states.into_iter().collect();
+    let mut threshold = initial_states.iter().map(&heuristic).min()?;
+    let mut expanded = 0;
+    let mut pushed = 0;
+    loop {
+        let mut next_threshold: Option<W> = None;
+        for initial_state in &initial_states {
+            // Each frame is a state on the current path, its distance from the start and its unexplored neighbors
+            let mut path: Vec<(S, W, Ns::IntoIter)> = Vec::new();
+            let mut candidate = Some((initial_state.clone(), zero::<W>()));
+            loop {
+                if let Some((state, distance)) = candidate.take() {
+                    pushed += 1;
+                    let estimate: W = [distance.clone(), heuristic(&state)].into_iter().sum();
+                    if estimate > threshold {
+                        if next_threshold.as_ref().is_none_or(|t| &estimate < t) {
+                            next_threshold = Some(estimate);
+                        }
+                    } else if is_complete(&state) {
+                        return Some(SearchResult {
+                            cost: distance,
+                            path: path.into_iter().map(|frame| frame.0).chain([state]).collect(),
+                            expanded,
+                            pushed,
+                        });
+                    } else {
+                        expanded += 1;
+                        let neighbors = get_neighbors(state.clone()).into_iter();
+                        path.push((state, distance, neighbors));
+                    }
+                }
+                let Some((_, distance, neighbors)) = path.last_mut() else {
+                    break;
+                };
+                let next = neighbors
+                    .next()
+                    .map(|(neighbor, edge)| (neighbor, [distance.clone(), edge].into_iter().sum::<W>()));
+                match next {
+                    Some((neighbor, _)) if path.iter().any(|frame| frame.0 == neighbor) => {}
+                    Some(next) => candidate = Some(next),
+                    None => {
+                        path.pop();
+                    }
+                }
+            }
+        }
+        threshold = next_threshold?;
+    }
+}
+
+struct Frontier<S, W> {
+    discovered_distance: HashMap<S, W>,
+    prev_state: HashMap<S, S>,
+    states_to_visit: BinaryHeap<Reverse<AStarState<S, W>>>,
+}
+impl<S: Hash + Eq + Clone, W: Sum + Clone + Ord> Frontier<S, W> {
+    fn new<H: Fn(&S) -> W>(initial_states: impl IntoIterator<Item = S>, heuristic: H) -> Self {
+        let mut frontier = Self {
+            discovered_distance: HashMap::default(),
+            prev_state: HashMap::default(),
+            states_to_visit: BinaryHeap::default(),
+        };
+        for initial_state in initial_states {
+            frontier.states_to_visit.push(Reverse(AStarState {
+                distance: AStarDisance {
+                    discovered_distance: zero(),
+                    heuristic: heuristic(&initial_state),
+                },
+                state: initial_state.clone(),
+            }));
+            frontier.discovered_distance.insert(initial_state, zero());
+        }
+        frontier
+    }
+    /// The lowest estimate left in the queue, once stale entries are dropped
+    fn min_estimate(&mut self) -> Option<W> {
+        while let Some(Reverse(top)) = self.states_to_visit.peek() {
+            if self.discovered_distance[&top.state] < top.distance.discovered_distance {
+                self.states_to_visit.pop();
+            } else {
+                return Some(top.distance.total_distance());
+            }
+        }
+        None
+    }
+    /// Expands the best state, recording any shorter meeting point with the other frontier.
+    /// Returns how many states were pushed
+    fn expand<N: Fn(S) -> Ns, Ns: IntoIterator<Item = (S, W)>, H: Fn(&S) -> W>(
+        &mut self,
+        get_neighbors: N,
+        heuristic: H,
+        other: &Self,
+        best_meeting: &mut Option<(W, S)>,
+    ) -> usize {
+        let Some(Reverse(current_state)) = self.states_to_visit.pop() else {
+            return 0;
+        };
+        let mut pushed = 0;
+        for (neighbor, distance) in get_neighbors(current_state.state.clone()) {
+            let tentative_discovered_distance: W =
+                [current_state.distance.discovered_distance.clone(), distance].into_iter().sum();
+            if self
+                .discovered_distance
+                .get(&neighbor)
+                .is_some_and(|d| d <= &tentative_discovered_distance)
+            {
+                continue;
+            }
+            if let Some(other_distance) = other.discovered_distance.get(&neighbor) {
+                let through_neighbor: W =
+                    [tentative_discovered_distance.clone(), other_distance.clone()].into_iter().sum();
+                if best_meeting.as_ref().is_none_or(|(best, _)| &through_neighbor < best) {
+                    *best_meeting = Some((through_neighbor, neighbor.clone()));
+                }
+            }
+            self.discovered_distance.insert(neighbor.clone(), tentative_discovered_distance.clone());
+            self.prev_state.insert(neighbor.clone(), current_state.state.clone());
+            self.states_to_visit.push(Reverse(AStarState {
+                distance: AStarDisance {
+                    discovered_distance: tentative_discovered_distance,
+                    heuristic: heuristic(&neighbor),
+                },
+                state: neighbor,
+            }));
+            pushed += 1;
+        }
+        pushed
+    }
+    /// The path from this frontier's initial states to `state`, ending at `state`
+    fn path_to(&self, state: &S) -> Vec<S> {
+        let mut path = vec![state.clone()];
+        let mut path_curr = state;
+        while let Some(prev) = self.prev_state.get(path_curr) {
+            path.push(prev.clone());
+            path_curr = prev;
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// A* run from both ends at once, meeting in the middle.
+///
+/// The backward search needs concrete goal states in place of `is_complete`,
+/// with `get_predecessors` and `reverse_heuristic` playing the part of `get_neighbors` and `heuristic`.
+#[allow(dead_code)]
+pub fn bidirectional_a_star<
+    S: Hash + Eq + Clone,
+    Ss: IntoIterator<Item = S>,
+    Gs: IntoIterator<Item = S>,
+    W: Sum + Clone + Ord,
+    N: Fn(S) -> Ns,
+    Ns: IntoIterator<Item = (S, W)>,
+    P: Fn(S) -> Ps,
+    Ps: IntoIterator<Item = (S, W)>,
+    H: Fn(&S) -> W,
+    R: Fn(&S) -> W,
+>(
+    initial_states: Ss,
+    goal_states: Gs,
+    get_neighbors: N,
+    get_predecessors: P,
+    heuristic: H,
+    reverse_heuristic: R,
+) -> Option<SearchResult<S, W>> {
+    let mut forward = Frontier::new(initial_states, &heuristic);
+    let mut backward = Frontier::new(goal_states, &reverse_heuristic);
+    let mut pushed = forward.states_to_visit.len() + backward.states_to_visit.len();
+    let mut expanded = 0;
+    let mut best_meeting = forward
+        .discovered_distance
+        .keys()
+        .find(|s| backward.discovered_distance.contains_key(*s))
+        .map(|s| (zero::<W>(), s.clone()));
+    while let Some((forward_estimate, backward_estimate)) = forward.min_estimate().zip(backward.min_estimate()) {
+        // Every path not found yet costs at least as much as either side's best estimate
+        if best_meeting
+            .as_ref()
+            .is_some_and(|(best, _)| &forward_estimate >= best || &backward_estimate >= best)
+        {
+            break;
+        }
+        expanded += 1;
+        pushed += if forward.states_to_visit.len() <= backward.states_to_visit.len() {
+            forward.expand(&get_neighbors, &heuristic, &backward, &mut best_meeting)
+        } else {
+            backward.expand(&get_predecessors, &reverse_heuristic, &forward, &mut best_meeting)
+        };
+    }
+    let (cost, meeting) = best_meeting?;
+    let mut path = forward.path_to(&meeting);
+    path.pop();
+    path.extend(backward.path_to(&meeting).into_iter().rev());
+    Some(SearchResult { cost, path, expanded, pushed })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{a_star, bidirectional_a_star, ida_star, reverse_a_star};
+
+    #[test]
+    fn shorter_path_found_later() {
+        // 0 -> 1 directly costs 10, but 0 -> 2 -> 3 -> 1 costs 3
+        let edges = [(0, 1, 10), (0, 2, 1), (2, 3, 1), (3, 1, 1), (1, 4, 1)];
+        let result = a_star(
+            [0],
+            |s| edges.iter().filter(move |e| e.0 == s).map(|e| (e.1, e.2)),
+            |_| 0u32,
+            |&s| s == 4,
+        )
+        .unwrap();
+        assert_eq!(result.path, [0, 2, 3, 1, 4]);
+        assert_eq!(result.cost, 4);
+        assert_eq!(result.expanded, 4);
+    }
+
+    #[test]
+    fn from_the_goal() {
+        let edges = [(0, 2, 1), (1, 2, 5), (2, 3, 1), (3, 4, 2)];
+        let predecessors = |s| edges.iter().filter(move |e| e.1 == s).map(|e| (e.0, e.2));
+        let result = reverse_a_star([4], predecessors, |_| 0u32, |&s| s < 2).unwrap();
+        assert_eq!(result.path, [0, 2, 3, 4]);
+        assert_eq!(result.cost, 4);
+    }
+
+    #[test]
+    fn variants_agree() {
+        // An 8x8 grid with a wall down column 4, open only in the bottom row
+        let neighbors = |(x, y): (i32, i32)| {
+            [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                .into_iter()
+                .filter(|&(x, y)| (0..8).contains(&x) && (0..8).contains(&y) && (x != 4 || y == 7))
+                .map(|s| (s, 1u32))
+        };
+        let to = |goal: (i32, i32)| move |s: &(i32, i32)| s.0.abs_diff(goal.0) + s.1.abs_diff(goal.1);
+        let expected = a_star([(0, 0)], neighbors, to((7, 0)), |&s| s == (7, 0)).unwrap();
+        assert_eq!(expected.cost, 21);
+
+        let iterative = ida_star([(0, 0)], neighbors, to((7, 0)), |&s| s == (7, 0)).unwrap();
+        assert_eq!(iterative.cost, expected.cost);
+        assert_eq!(iterative.path.len(), expected.path.len());
+
+        let bidirectional = bidirectional_a_star([(0, 0)], [(7, 0)], neighbors, neighbors, to((7, 0)), to((0, 0))).unwrap();
+        assert_eq!(bidirectional.cost, expected.cost);
+        assert_eq!(bidirectional.path.len(), expected.path.len());
+        assert_eq!(bidirectional.path.first(), Some(&(0, 0)));
+        assert_eq!(bidirectional.path.last(), Some(&(7, 0)));
+        assert!(bidirectional.path.windows(2).all(|w| w[0].0.abs_diff(w[1].0) + w[0].1.abs_diff(w[1].1) == 1));
+    }
+}
@@ -1,8 +1,13 @@
 use std::{
-    borrow::{Borrow, BorrowMut},
+    cmp::Ordering,
     fmt::Debug,
+    hash::{Hash, Hasher},
+    iter::FusedIterator,
     marker::PhantomData,
-    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Index, IndexMut, Not},
+    ops::{
+        BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Index, IndexMut, Not, Range, Sub,
+        SubAssign,
+    },
 };
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -67,35 +72,194 @@ impl<const N: usize, const DEFAULT_BYTE: u8> DefaultedBytes<N, DEFAULT_BYTE> {
     }
 }
 
-#[derive(Hash)]
-pub struct BitSet<T=usize, B = Vec<u8>> {
-    bytes: B,
+/// A machine word that [`BitSet`] stores its bits in
+pub trait Word:
+    Copy
+    + Ord
+    + Hash
+    + Debug
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + Not<Output = Self>
+    + BitAndAssign
+    + BitOrAssign
+    + BitXorAssign
+{
+    const BITS: usize;
+    const ZERO: Self;
+    const ONES: Self;
+    fn count_ones(self) -> u32;
+    fn trailing_zeros(self) -> u32;
+    fn leading_zeros(self) -> u32;
+    /// Only the bit at `index`
+    fn bit(index: usize) -> Self;
+    /// Every bit from `index` upwards
+    fn from_bit(index: usize) -> Self;
+    fn to_u128(self) -> u128;
+    /// Only the lowest bits that fit
+    fn from_u128_truncated(bits: u128) -> Self;
+}
+macro_rules! impl_word {
+    ($($word:ty),*) => {$(
+        impl Word for $word {
+            const BITS: usize = <$word>::BITS as usize;
+            const ZERO: Self = 0;
+            const ONES: Self = <$word>::MAX;
+            #[inline]
+            fn count_ones(self) -> u32 {
+                self.count_ones()
+            }
+            #[inline]
+            fn trailing_zeros(self) -> u32 {
+                self.trailing_zeros()
+            }
+            #[inline]
+            fn leading_zeros(self) -> u32 {
+                self.leading_zeros()
+            }
+            #[inline]
+            fn bit(index: usize) -> Self {
+                1 << index
+            }
+            #[inline]
+            fn from_bit(index: usize) -> Self {
+                <$word>::MAX << index
+            }
+            #[inline]
+            fn to_u128(self) -> u128 {
+                self as u128
+            }
+            #[inline]
+            fn from_u128_truncated(bits: u128) -> Self {
+                bits as $word
+            }
+        }
+    )*};
+}
+impl_word!(u8, u16, u32, u64, u128);
+
+/// Backing storage for a [`BitSet`], as a slice of words
+pub trait BitStore {
+    type Word: Word;
+    fn words(&self) -> &[Self::Word];
+}
+/// Backing storage that a [`BitSet`] can modify
+pub trait BitStoreMut: BitStore {
+    fn words_mut(&mut self) -> &mut [Self::Word];
+    /// Makes room for at least `word_count` words, returning whether there is now room
+    fn reserve_words(&mut self, word_count: usize) -> bool {
+        word_count <= self.words().len()
+    }
+}
+impl<W: Word, const N: usize> BitStore for [W; N] {
+    type Word = W;
+    fn words(&self) -> &[W] {
+        self
+    }
+}
+impl<W: Word, const N: usize> BitStoreMut for [W; N] {
+    fn words_mut(&mut self) -> &mut [W] {
+        self
+    }
+}
+impl<W: Word> BitStore for Vec<W> {
+    type Word = W;
+    fn words(&self) -> &[W] {
+        self
+    }
+}
+/// Grows to fit whatever is inserted
+impl<W: Word> BitStoreMut for Vec<W> {
+    fn words_mut(&mut self) -> &mut [W] {
+        self
+    }
+    fn reserve_words(&mut self, word_count: usize) -> bool {
+        if word_count > self.len() {
+            self.resize(word_count, W::ZERO);
+        }
+        true
+    }
+}
+impl<const N: usize, const DEFAULT_BYTE: u8> BitStore for DefaultedBytes<N, DEFAULT_BYTE> {
+    type Word = u8;
+    fn words(&self) -> &[u8] {
+        &self.0
+    }
+}
+impl<const N: usize, const DEFAULT_BYTE: u8> BitStoreMut for DefaultedBytes<N, DEFAULT_BYTE> {
+    fn words_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+impl<S: BitStore> BitStore for &S {
+    type Word = S::Word;
+    fn words(&self) -> &[S::Word] {
+        (**self).words()
+    }
+}
+
+/// A set of values stored as one bit per `usize` index, packed into the words of `B`.
+///
+/// `Vec` storage grows on insertion, while arrays and [`DefaultedBytes`] have a fixed capacity
+/// and panic when something past it is inserted.
+///
+/// The set ranges over `0..universe`, which is what `!` complements within.
+/// It starts as the whole store (or `with_capacity`) and grows to cover anything inserted,
+/// and every bit past it is kept clear.
+///
+/// Sets compare and hash by their members alone, whatever their store or universe.
+/// So equal sets with different universes have different complements, `a == b` doesn't make `!a == !b`.
+/// They order like the integers their bits spell out, which is also how they convert to and from `u64` and `u128`.
+pub struct BitSet<T = usize, B = Vec<u64>> {
+    words: B,
+    universe: usize,
     phantom_elements: PhantomData<T>,
 }
 
-impl<T: Into<usize> + Debug, B: IndexMut<usize, Output = u8>> Extend<T> for BitSet<T, B>
-where
-    Self: Debug,
-{
+impl<T: Into<usize>, B: BitStoreMut> Extend<T> for BitSet<T, B> {
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
         for item in iter {
-            // dbg!(&item);
-            // dbg!(&self);
             self.insert(item);
         }
     }
 }
-impl<T, B: PartialEq> PartialEq for BitSet<T, B> {
-    fn eq(&self, other: &Self) -> bool {
-        self.bytes == other.bytes
+impl<T: Into<usize>, B: BitStoreMut + Default> FromIterator<T> for BitSet<T, B> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::default();
+        set.extend(iter);
+        set
+    }
+}
+impl<T, B: BitStore, R: BitStore<Word = B::Word>> PartialEq<BitSet<T, R>> for BitSet<T, B> {
+    fn eq(&self, other: &BitSet<T, R>) -> bool {
+        self.trimmed_words() == other.trimmed_words()
+    }
+}
+impl<T, B: BitStore> Eq for BitSet<T, B> {}
+impl<T, B: BitStore> Hash for BitSet<T, B> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.trimmed_words().hash(state);
+    }
+}
+impl<T, B: BitStore> Ord for BitSet<T, B> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (self.trimmed_words(), other.trimmed_words());
+        // More words means a higher top bit, otherwise the highest differing word decides
+        a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+    }
+}
+impl<T, B: BitStore> PartialOrd for BitSet<T, B> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
-impl<T, B: Eq> Eq for BitSet<T, B> {}
 impl<T, B: Copy> Copy for BitSet<T, B> {}
 impl<T, B: Clone> Clone for BitSet<T, B> {
     fn clone(&self) -> Self {
         Self {
-            bytes: self.bytes.clone(),
+            words: self.words.clone(),
+            universe: self.universe,
             phantom_elements: PhantomData,
         }
     }
@@ -103,308 +267,460 @@ impl<T, B: Clone> Clone for BitSet<T, B> {
 impl<T, B: Debug> Debug for BitSet<T, B> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("BitSet")
-            .field("bytes", &self.bytes)
+            .field("words", &self.words)
+            .field("universe", &self.universe)
             .finish()
     }
 }
-impl<T> BitSet<T, Vec<u8>> {
+impl<T, B: BitStoreMut + Default> BitSet<T, B> {
+    /// An empty set over the values `0..universe`, with room for all of them.
+    /// Panics if a fixed size store is too small
     #[inline]
-    pub fn with_capacity(capacity: usize) -> Self {
+    pub fn with_capacity(universe: usize) -> Self {
+        let mut words = B::default();
+        assert!(
+            words.reserve_words(universe.div_ceil(B::Word::BITS)),
+            "{universe} values don't fit in the store"
+        );
         Self {
-            bytes: vec![0; (capacity + 7) & (!7)],
+            words,
+            universe,
             phantom_elements: PhantomData,
         }
     }
 }
-impl<T: Into<usize>> BitSet<T, Vec<u8>> {
+impl<T: Into<usize>, B: BitStoreMut + Default> BitSet<T, B> {
     #[inline]
     pub fn with_max(value: T) -> Self {
         Self::with_capacity(value.into() + 1)
     }
 }
-impl<T, B: Default> Default for BitSet<T, B> {
+impl<T, B: BitStore + Default> Default for BitSet<T, B> {
     fn default() -> Self {
+        let words = B::default();
         Self {
-            bytes: Default::default(),
+            universe: words.words().len() * B::Word::BITS,
+            words,
             phantom_elements: PhantomData,
         }
     }
 }
 
 pub struct BitSetIndex {
-    byte_index: usize,
-    bitmask: u8,
+    word_index: usize,
+    bit_index: usize,
 }
 
 #[inline]
-fn to_bitset_index(index: usize) -> BitSetIndex {
-    let div = index / 8;
-    let rem = index % 8;
+fn to_bitset_index<W: Word>(index: usize) -> BitSetIndex {
     BitSetIndex {
-        byte_index: div,
-        bitmask: 1u8 << rem,
+        word_index: index / W::BITS,
+        bit_index: index % W::BITS,
     }
 }
 
-impl<T: Into<usize>, B: IndexMut<usize, Output = u8>> BitSet<T, B> {
+impl<T: Into<usize>, B: BitStore> BitSet<T, B> {
     pub fn contains(&self, value: T) -> bool {
-        let index = to_bitset_index(value.into());
-        (self.bytes[index.byte_index] & index.bitmask) != 0
+        let index = to_bitset_index::<B::Word>(value.into());
+        self.words
+            .words()
+            .get(index.word_index)
+            .is_some_and(|&word| word & B::Word::bit(index.bit_index) != B::Word::ZERO)
     }
-
-    // #[cfg(test)]
-    // pub fn toggle(&mut self,value:T)->bool {
-    //     let index = to_bitset_index(value.into());
-
-    //     let byte = &mut self.bytes[index.byte_index];
-    //     let byte_value = *byte;
-
-    //     let contained = (byte_value & index.bitmask) != 0;
-    //     *byte = byte_value ^ index.bitmask;
-    //     contained
-    // }
-
+}
+impl<T: Into<usize>, B: BitStoreMut> BitSet<T, B> {
+    /// Adds `value`, returning whether it was already there
     pub fn insert(&mut self, value: T) -> bool {
-        let index = to_bitset_index(value.into());
-
-        let byte = &mut self.bytes[index.byte_index];
-        let byte_value = *byte;
+        let value = value.into();
+        self.universe = self.universe.max(value + 1);
+        let index = to_bitset_index::<B::Word>(value);
+        self.words.reserve_words(index.word_index + 1);
 
-        let contained = (byte_value & index.bitmask) != 0;
-        *byte = byte_value | index.bitmask;
+        let word = &mut self.words.words_mut()[index.word_index];
+        let bit = B::Word::bit(index.bit_index);
+        let contained = *word & bit != B::Word::ZERO;
+        *word |= bit;
         contained
     }
+    /// Takes out `value`, returning whether it was there
     pub fn remove(&mut self, value: T) -> bool {
-        let index = to_bitset_index(value.into());
-
-        let byte = &mut self.bytes[index.byte_index];
-        let byte_value = *byte;
-
-        let contained = (byte_value & index.bitmask) != 0;
-        *byte = byte_value & (!index.bitmask);
+        let index = to_bitset_index::<B::Word>(value.into());
+        let Some(word) = self.words.words_mut().get_mut(index.word_index) else {
+            return false;
+        };
+        let bit = B::Word::bit(index.bit_index);
+        let contained = *word & bit != B::Word::ZERO;
+        *word &= !bit;
         contained
     }
 }
-impl<T, B> BitSet<T, B>
-where
-    for<'a> &'a B: IntoIterator,
-    for<'a> <&'a B as IntoIterator>::Item: Borrow<u8>,
-{
-    pub fn iter(&self) -> BitsetIterator<T, <&B as IntoIterator>::IntoIter> {
-        BitsetIterator::new(&self.bytes)
+impl<T, B: BitStore> BitSet<T, B> {
+    pub fn iter(&self) -> BitsetIterator<T, &B> {
+        BitsetIterator::new(&self.words)
+    }
+    /// The members within `range`, found a word at a time
+    pub fn iter_range(&self, range: Range<usize>) -> BitsetIterator<T, &B> {
+        let mut iter = self.iter();
+        iter.end = range.end.min(iter.end);
+        iter.start = range.start.min(iter.end);
+        iter
+    }
+    /// The number of values the set can hold without growing
+    pub fn capacity(&self) -> usize {
+        self.words.words().len() * B::Word::BITS
+    }
+    /// The set ranges over the values below this
+    pub fn universe(&self) -> usize {
+        self.universe
+    }
+    /// The words up to the last one with a member in it
+    fn trimmed_words(&self) -> &[B::Word] {
+        let words = self.words.words();
+        let used = words.iter().rposition(|&word| word != B::Word::ZERO).map_or(0, |last| last + 1);
+        &words[..used]
+    }
+    pub fn len(&self) -> usize {
+        self.words.words().iter().map(|word| word.count_ones() as usize).sum()
     }
     pub fn is_empty(&self) -> bool {
-        self.bytes.borrow().into_iter().all(|b|*b.borrow() == 0)
+        self.words.words().iter().all(|&word| word == B::Word::ZERO)
     }
-}
-impl<T, B> BitSet<T, B>
-where
-    for<'a> &'a mut B: IntoIterator,
-    for<'a> <&'a mut B as IntoIterator>::Item: BorrowMut<u8>,
-{
-    pub fn clear(&mut self) {
-        self.bytes.borrow_mut().into_iter().for_each(|mut b|*b.borrow_mut()=0);
+    /// Whether every member is also in `other`
+    pub fn is_subset<R: BitStore<Word = B::Word>>(&self, other: &BitSet<T, R>) -> bool {
+        let other = other.words.words();
+        self.words.words().iter().enumerate().all(|(i, &word)| {
+            let other_word = other.get(i).copied().unwrap_or(B::Word::ZERO);
+            word & !other_word == B::Word::ZERO
+        })
+    }
+    /// Whether every member of `other` is also in this set
+    pub fn is_superset<R: BitStore<Word = B::Word>>(&self, other: &BitSet<T, R>) -> bool {
+        other.is_subset(self)
+    }
+    pub fn is_disjoint<R: BitStore<Word = B::Word>>(&self, other: &BitSet<T, R>) -> bool {
+        self.words.words().iter().zip(other.words.words()).all(|(&a, &b)| a & b == B::Word::ZERO)
     }
 }
-impl<T, B> BitAndAssign for BitSet<T, B>
-where
-    for<'a> &'a B: IntoIterator,
-    for<'a> <&'a B as IntoIterator>::Item: Borrow<u8>,
-    for<'a> &'a mut B: IntoIterator,
-    for<'a> <&'a mut B as IntoIterator>::Item: BorrowMut<u8>,
-{
-    fn bitand_assign(&mut self, rhs: Self) {
-        for (mut a, b) in
-            IntoIterator::into_iter(&mut self.bytes).zip(IntoIterator::into_iter(&rhs.bytes))
-        {
-            *a.borrow_mut() &= *b.borrow();
-        }
+impl<T: From<usize>, B: BitStore> BitSet<T, B> {
+    /// Like [`BitSet::iter`], but yielding each member directly
+    pub fn members(&self) -> Members<T, &B> {
+        self.iter().members()
+    }
+    pub fn into_members(self) -> Members<T, B> {
+        BitsetIterator::new(self.words).members()
     }
 }
-impl<T, B> BitOrAssign for BitSet<T, B>
-where
-    for<'a> &'a B: IntoIterator,
-    for<'a> <&'a B as IntoIterator>::Item: Borrow<u8>,
-    for<'a> &'a mut B: IntoIterator,
-    for<'a> <&'a mut B as IntoIterator>::Item: BorrowMut<u8>,
-{
-    fn bitor_assign(&mut self, rhs: Self) {
-        for (mut a, b) in
-            IntoIterator::into_iter(&mut self.bytes).zip(IntoIterator::into_iter(&rhs.bytes))
-        {
-            *a.borrow_mut() |= *b.borrow();
-        }
+impl<T: TryFrom<usize>, B: BitStore> BitSet<T, B> {
+    /// The smallest member
+    pub fn first(&self) -> Option<Result<T, T::Error>> {
+        self.iter().next()
+    }
+    /// The largest member
+    pub fn last(&self) -> Option<Result<T, T::Error>> {
+        self.iter().next_back()
     }
 }
-impl<T, B> BitXorAssign for BitSet<T, B>
-where
-    for<'a> &'a B: IntoIterator,
-    for<'a> <&'a B as IntoIterator>::Item: Borrow<u8>,
-    for<'a> &'a mut B: IntoIterator,
-    for<'a> <&'a mut B as IntoIterator>::Item: BorrowMut<u8>,
-{
-    fn bitxor_assign(&mut self, rhs: Self) {
-        for (mut a, b) in
-            IntoIterator::into_iter(&mut self.bytes).zip(IntoIterator::into_iter(&rhs.bytes))
-        {
-            *a.borrow_mut() ^= *b.borrow();
+impl<T, B: BitStoreMut> BitSet<T, B> {
+    pub fn clear(&mut self) {
+        self.words.words_mut().fill(B::Word::ZERO);
+    }
+    /// Applies `op` to every pair of words, treating words past the end of either set as empty.
+    /// Operations that can add members (`grow`) first make room for all of `rhs`, panicking if a fixed store can't hold its members
+    #[inline]
+    fn zip_words<R: BitStore<Word = B::Word>>(
+        &mut self,
+        rhs: &BitSet<T, R>,
+        grow: bool,
+        op: impl Fn(&mut B::Word, B::Word),
+    ) {
+        if grow {
+            // A fixed store only has to fit the members, and then its universe stops at its capacity
+            if !self.words.reserve_words(rhs.words.words().len()) {
+                assert!(
+                    self.words.reserve_words(rhs.trimmed_words().len()),
+                    "{} values don't fit in the store",
+                    rhs.universe
+                );
+            }
+            self.universe = self.universe.max(rhs.universe).min(self.capacity());
+        }
+        let rhs = rhs.words.words();
+        let words = self.words.words_mut();
+        let overlap = words.len().min(rhs.len());
+        // Plain loops over equal length slices, so they can be vectorised
+        for (a, &b) in words[..overlap].iter_mut().zip(&rhs[..overlap]) {
+            op(a, b);
+        }
+        for a in &mut words[overlap..] {
+            op(a, B::Word::ZERO);
         }
     }
 }
-impl<T, B> BitAnd for BitSet<T, B>
-where
-    for<'a> &'a B: IntoIterator,
-    for<'a> <&'a B as IntoIterator>::Item: Borrow<u8>,
-    for<'a> &'a mut B: IntoIterator,
-    for<'a> <&'a mut B as IntoIterator>::Item: BorrowMut<u8>,
-{
-    type Output = Self;
-    fn bitand(mut self, rhs: Self) -> Self::Output {
-        self &= rhs;
-        self
-    }
+/// Implements a set operator for every mix of owned and borrowed sets, over any stores with the same word
+macro_rules! set_operator {
+    ($Op:ident::$op:ident, $OpAssign:ident::$op_assign:ident, grow: $grow:expr, |$a:ident, $b:ident| $combine:expr) => {
+        impl<T, B: BitStoreMut, R: BitStore<Word = B::Word>> $OpAssign<&BitSet<T, R>> for BitSet<T, B> {
+            fn $op_assign(&mut self, rhs: &BitSet<T, R>) {
+                self.zip_words(rhs, $grow, |$a, $b| *$a = $combine);
+            }
+        }
+        impl<T, B: BitStoreMut, R: BitStore<Word = B::Word>> $OpAssign<BitSet<T, R>> for BitSet<T, B> {
+            fn $op_assign(&mut self, rhs: BitSet<T, R>) {
+                self.$op_assign(&rhs);
+            }
+        }
+        impl<T, B: BitStoreMut, R: BitStore<Word = B::Word>> $Op<BitSet<T, R>> for BitSet<T, B> {
+            type Output = Self;
+            fn $op(mut self, rhs: BitSet<T, R>) -> Self::Output {
+                self.$op_assign(&rhs);
+                self
+            }
+        }
+        impl<T, B: BitStoreMut, R: BitStore<Word = B::Word>> $Op<&BitSet<T, R>> for BitSet<T, B> {
+            type Output = Self;
+            fn $op(mut self, rhs: &BitSet<T, R>) -> Self::Output {
+                self.$op_assign(rhs);
+                self
+            }
+        }
+        impl<T, B: BitStoreMut + Clone, R: BitStore<Word = B::Word>> $Op<&BitSet<T, R>> for &BitSet<T, B> {
+            type Output = BitSet<T, B>;
+            fn $op(self, rhs: &BitSet<T, R>) -> Self::Output {
+                let mut result = self.clone();
+                result.$op_assign(rhs);
+                result
+            }
+        }
+    };
 }
-impl<T, B> BitOr for BitSet<T, B>
-where
-    for<'a> &'a B: IntoIterator,
-    for<'a> <&'a B as IntoIterator>::Item: Borrow<u8>,
-    for<'a> &'a mut B: IntoIterator,
-    for<'a> <&'a mut B as IntoIterator>::Item: BorrowMut<u8>,
-{
+set_operator!(BitAnd::bitand, BitAndAssign::bitand_assign, grow: false, |a, b| *a & b);
+set_operator!(BitOr::bitor, BitOrAssign::bitor_assign, grow: true, |a, b| *a | b);
+set_operator!(BitXor::bitxor, BitXorAssign::bitxor_assign, grow: true, |a, b| *a ^ b);
+// The difference
+set_operator!(Sub::sub, SubAssign::sub_assign, grow: false, |a, b| *a & !b);
+
+/// Every value in the universe that isn't a member.
+/// This depends on the universe as well as the members, unlike equality
+impl<T, B: BitStoreMut> Not for BitSet<T, B> {
     type Output = Self;
-    fn bitor(mut self, rhs: Self) -> Self::Output {
-        self |= rhs;
+    fn not(mut self) -> Self::Output {
+        let universe = self.universe;
+        for (i, word) in self.words.words_mut().iter_mut().enumerate() {
+            let word_start = i * B::Word::BITS;
+            *word = if word_start + B::Word::BITS <= universe {
+                !*word
+            } else if word_start < universe {
+                !*word & !B::Word::from_bit(universe - word_start)
+            } else {
+                B::Word::ZERO
+            };
+        }
         self
     }
 }
-impl<T, B> BitXor for BitSet<T, B>
-where
-    for<'a> &'a B: IntoIterator,
-    for<'a> <&'a B as IntoIterator>::Item: Borrow<u8>,
-    for<'a> &'a mut B: IntoIterator,
-    for<'a> <&'a mut B as IntoIterator>::Item: BorrowMut<u8>,
-{
-    type Output = Self;
-    fn bitxor(mut self, rhs: Self) -> Self::Output {
-        self ^= rhs;
-        self
+impl<T, B: BitStoreMut + Clone> Not for &BitSet<T, B> {
+    type Output = BitSet<T, B>;
+    fn not(self) -> Self::Output {
+        !self.clone()
     }
 }
-impl<T, B> Not for BitSet<T, B>
-where
-    for<'a> &'a mut B: IntoIterator,
-    for<'a> <&'a mut B as IntoIterator>::Item: BorrowMut<u8>,
-{
-    type Output = Self;
-    fn not(mut self) -> Self::Output {
-        for mut byte in &mut self.bytes {
-            let mut_bute = byte.borrow_mut();
-            *mut_bute = !*mut_bute;
-        }
-        self
+impl<T: TryFrom<usize>, B: BitStore> IntoIterator for BitSet<T, B> {
+    type Item = Result<T, T::Error>;
+
+    type IntoIter = BitsetIterator<T, B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        BitsetIterator::new(self.words)
     }
 }
-impl<T: TryFrom<usize>, B: IntoIterator<Item = u8>> IntoIterator for BitSet<T, B>
-where
-    B::Item: Borrow<u8>,
-{
+impl<'a, T: TryFrom<usize>, B: BitStore> IntoIterator for &'a BitSet<T, B> {
     type Item = Result<T, T::Error>;
 
-    type IntoIter = BitsetIterator<T, B::IntoIter>;
+    type IntoIter = BitsetIterator<T, &'a B>;
 
     fn into_iter(self) -> Self::IntoIter {
-        let mut byte_iter = self.bytes.into_iter();
-        BitsetIterator {
-            current_byte: byte_iter.next(),
-            phantom_data: PhantomData,
-            byte_iter,
-            bits_consumed: 0,
+        self.iter()
+    }
+}
+
+/// The set of the bits set in `bits`, like [`Extend`]ing a default set with them.
+/// Panics if a fixed size store can't hold the highest one
+impl<T, B: BitStoreMut + Default> From<u128> for BitSet<T, B> {
+    fn from(bits: u128) -> Self {
+        let mut set = Self::default();
+        let significant_bits = (u128::BITS - bits.leading_zeros()) as usize;
+        let word_count = significant_bits.div_ceil(B::Word::BITS);
+        assert!(set.words.reserve_words(word_count), "{bits:#x} doesn't fit in the store");
+        set.universe = set.universe.max(significant_bits);
+        for (i, word) in set.words.words_mut()[..word_count].iter_mut().enumerate() {
+            *word = B::Word::from_u128_truncated(bits >> (i * B::Word::BITS));
+        }
+        set
+    }
+}
+/// The set of the bits set in `bits`, like [`Extend`]ing a default set with them.
+/// Panics if a fixed size store can't hold the highest one
+impl<T, B: BitStoreMut + Default> From<u64> for BitSet<T, B> {
+    fn from(bits: u64) -> Self {
+        Self::from(bits as u128)
+    }
+}
+/// Fails if there is a member too big for the integer
+impl<T, B: BitStore> TryFrom<&BitSet<T, B>> for u128 {
+    type Error = ();
+    fn try_from(set: &BitSet<T, B>) -> Result<Self, Self::Error> {
+        let words = set.trimmed_words();
+        if words.len() * B::Word::BITS > u128::BITS as usize {
+            return Err(());
+        }
+        Ok(words
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, word)| bits | word.to_u128() << (i * B::Word::BITS)))
+    }
+}
+/// Fails if there is a member too big for the integer
+impl<T, B: BitStore> TryFrom<&BitSet<T, B>> for u64 {
+    type Error = ();
+    fn try_from(set: &BitSet<T, B>) -> Result<Self, Self::Error> {
+        u128::try_from(set)?.try_into().map_err(|_| ())
+    }
+}
+
+/// Packs a few small fields into one `u128`, making a cheap, exact key for memoising search states
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PackedKey {
+    bits: u128,
+    bits_used: u32,
+}
+impl PackedKey {
+    /// Appends `value` in the next `bits` bits, or `None` if it needs more than that or the key is full
+    pub fn push(self, value: u128, bits: u32) -> Option<Self> {
+        let bits_used = self.bits_used.checked_add(bits).filter(|&used| used <= u128::BITS)?;
+        if value.checked_shr(bits).unwrap_or(0) != 0 {
+            return None;
         }
+        Some(Self {
+            bits: self.bits | value.checked_shl(self.bits_used).unwrap_or(0),
+            bits_used,
+        })
+    }
+    /// Appends a set in as many bits as its universe
+    pub fn push_set<T, B: BitStore>(self, set: &BitSet<T, B>) -> Option<Self> {
+        self.push(u128::try_from(set).ok()?, set.universe().try_into().ok()?)
+    }
+    #[allow(dead_code)]
+    pub fn bits(&self) -> u128 {
+        self.bits
     }
 }
 
-pub struct BitsetIterator<T, I> {
-    current_byte: Option<u8>,
-    bits_consumed: usize,
-    byte_iter: I,
+/// The members of a set from both ends, skipping a whole word at a time with `trailing_zeros`/`leading_zeros`
+pub struct BitsetIterator<T, S> {
+    words: S,
+    /// Every member left is within `start..end`
+    start: usize,
+    end: usize,
     phantom_data: PhantomData<T>,
 }
-impl<T, I: Iterator> BitsetIterator<T, I>
-where
-    I::Item: Borrow<u8>,
-{
-    pub fn new<II: IntoIterator<IntoIter = I>>(iter: II) -> Self {
-        let mut byte_iter = iter.into_iter();
+impl<T, S: BitStore> BitsetIterator<T, S> {
+    pub fn new(words: S) -> Self {
+        let end = words.words().len() * S::Word::BITS;
         BitsetIterator {
-            current_byte: byte_iter.next().map(|b| *b.borrow()),
+            words,
+            start: 0,
+            end,
             phantom_data: PhantomData,
-            byte_iter,
-            bits_consumed: 0,
         }
     }
+    fn next_index(&mut self) -> Option<usize> {
+        while self.start < self.end {
+            let word_start = self.start - self.start % S::Word::BITS;
+            let word = self.words.words()[self.start / S::Word::BITS] & S::Word::from_bit(self.start % S::Word::BITS);
+            if word != S::Word::ZERO {
+                let index = word_start + word.trailing_zeros() as usize;
+                if index >= self.end {
+                    break;
+                }
+                self.start = index + 1;
+                return Some(index);
+            }
+            self.start = word_start + S::Word::BITS;
+        }
+        self.start = self.end;
+        None
+    }
+    fn next_back_index(&mut self) -> Option<usize> {
+        while self.start < self.end {
+            let last = self.end - 1;
+            let word_start = last - last % S::Word::BITS;
+            // Every bit up to and including `last`
+            let mask = !S::Word::from_bit(last % S::Word::BITS) | S::Word::bit(last % S::Word::BITS);
+            let word = self.words.words()[last / S::Word::BITS] & mask;
+            if word != S::Word::ZERO {
+                let index = word_start + S::Word::BITS - 1 - word.leading_zeros() as usize;
+                if index < self.start {
+                    break;
+                }
+                self.end = index;
+                return Some(index);
+            }
+            self.end = word_start;
+        }
+        self.end = self.start;
+        None
+    }
 }
-impl<T, I> Debug for BitsetIterator<T, I> {
+impl<T, S> Debug for BitsetIterator<T, S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("BitsetIterator")
-            .field("current_byte", &self.current_byte)
-            .field("bits_consumed", &self.bits_consumed)
+            .field("start", &self.start)
+            .field("end", &self.end)
             .finish()
     }
 }
 
-#[inline]
-pub fn unborrow_byte<B: Borrow<u8>>(byte: B) -> u8 {
-    *byte.borrow()
-    // let byte = *byte.borrow();
-    // println!("Read byte: {:#b}",byte);
-    // byte
-}
-
-impl<T: TryFrom<usize>, I: Iterator> Iterator for BitsetIterator<T, I>
-where
-    I::Item: Borrow<u8>,
-{
+impl<T: TryFrom<usize>, S: BitStore> Iterator for BitsetIterator<T, S> {
     type Item = Result<T, T::Error>;
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            let current_byte = self.current_byte?;
-            let trailing = current_byte.trailing_zeros() as u8;
-            // dbg!(&self,trailing);
-            // If the new index isn't a multiple of 8, (only partially into the current byte)
-            // Or the offset is 0 (current bit is set)
-            if trailing == 8 {
-                self.current_byte = self.byte_iter.next().map(unborrow_byte); // Read next byte
-                self.bits_consumed = (self.bits_consumed & !0b111usize) + 0b1000usize;
-            // Next multiple of 8 because next byte
-            } else {
-                let new_index = trailing as usize + self.bits_consumed;
-                self.bits_consumed = new_index;
-                if trailing == 0 || new_index & 0b111usize != 0 {
-                    let next_byte = current_byte
-                        .checked_shr((trailing + 1) as u32)
-                        .unwrap_or_default(); // Consume the (presumably set) bit
-                                              // println!("Next byte: {:#b}",next_byte);
-                    if next_byte == 0 {
-                        self.current_byte = self.byte_iter.next().map(unborrow_byte); // Read next byte
-                        self.bits_consumed = (self.bits_consumed & !0b111usize) + 0b1000usize;
-                    // Next multiple of 8 because next byte
-                    // dbg!(&self.current_byte);
-                    } else {
-                        self.bits_consumed += 1;
-                        self.current_byte = Some(next_byte);
-                    }
-                    // self.current_byte = Some(next_byte);
-
-                    return Some(T::try_from(new_index));
-                } else {
-                    self.current_byte = self.byte_iter.next().map(unborrow_byte);
-                    // Read next byte
-                }
-            }
-        }
+        self.next_index().map(T::try_from)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end - self.start))
+    }
+}
+impl<T: TryFrom<usize>, S: BitStore> DoubleEndedIterator for BitsetIterator<T, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.next_back_index().map(T::try_from)
+    }
+}
+impl<T: TryFrom<usize>, S: BitStore> FusedIterator for BitsetIterator<T, S> {}
+impl<T: From<usize>, S> BitsetIterator<T, S> {
+    /// Yields the members directly, as every index converts to a `T`
+    pub fn members(self) -> Members<T, S> {
+        Members(self)
     }
 }
 
+/// The members of a set whose element type every index converts to, so there are no errors to handle
+#[derive(Debug)]
+pub struct Members<T, S>(BitsetIterator<T, S>);
+impl<T: From<usize>, S: BitStore> Iterator for Members<T, S> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.next_index().map(T::from)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+impl<T: From<usize>, S: BitStore> DoubleEndedIterator for Members<T, S> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.next_back_index().map(T::from)
+    }
+}
+impl<T: From<usize>, S: BitStore> FusedIterator for Members<T, S> {}
+
 #[cfg(test)]
 mod tests {
 
@@ -415,9 +731,12 @@ mod tests {
             Err(infallible_error) => match infallible_error {},
         }
     }
-    use std::convert::Infallible;
+    use std::{
+        convert::Infallible,
+        hash::{DefaultHasher, Hash, Hasher},
+    };
 
-    use super::BitSet;
+    use super::{BitSet, PackedKey};
 
     const PRIMES: [usize; 25] = [
         2, 3, 5, 7, 9, 11, 13, 17, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 91,
@@ -476,4 +795,109 @@ mod tests {
         bitset.remove(2);
         assert!(!bitset.contains(2));
     }
+
+    #[test]
+    pub fn test_words() {
+        let mut bitset = BitSet::<usize>::default();
+        bitset.extend([3, 64, 65, 200]);
+        assert_eq!(bitset.capacity(), 256);
+        assert_eq!(bitset.len(), 4);
+        assert_eq!(bitset.first(), Some(Ok(3)));
+        assert_eq!(bitset.last(), Some(Ok(200)));
+        assert!(bitset.members().rev().eq([200, 65, 64, 3]));
+        assert!(bitset.iter_range(4..200).members().eq([64, 65]));
+        assert!(!bitset.contains(1000) && !bitset.remove(1000));
+
+        let mut small = BitSet::<usize, [u64; 1]>::default();
+        small.extend([3, 5]);
+        let mut grown = BitSet::<usize>::with_capacity(1);
+        grown |= BitSet::<usize>::with_capacity(0);
+        grown.insert(5);
+        grown &= bitset.clone();
+        assert!(grown.is_empty());
+        assert!(small.into_members().eq([3, 5]));
+        assert_eq!((bitset ^ BitSet::<usize>::with_max(3)).len(), 4);
+        assert!((small & BitSet::<usize, [u64; 1]>::default()).is_empty());
+    }
+
+    #[test]
+    pub fn test_set_algebra() {
+        let mut evens = BitSet::<usize>::with_capacity(20);
+        evens.extend((0..20).step_by(2));
+        let mut small_evens = BitSet::<usize, [u64; 1]>::default();
+        small_evens.extend([2, 4]);
+        let mut odds = !&evens;
+        assert!(odds.members().eq((1..20).step_by(2)));
+
+        assert!(small_evens.is_subset(&evens) && evens.is_superset(&small_evens));
+        assert!(!evens.is_subset(&small_evens) && evens.is_disjoint(&odds));
+        assert_eq!((&evens | &odds).len(), 20);
+        assert_eq!((&evens & &small_evens).len(), 2);
+        assert_eq!((&evens - &small_evens).len(), 8);
+        assert_eq!((&evens ^ &small_evens).len(), 8);
+
+        // Growing the universe leaves room for more non-members
+        odds.insert(99);
+        assert_eq!((!odds).len(), 100 - 11);
+        assert_eq!((!BitSet::<usize>::with_capacity(10)).len(), 10);
+    }
+
+    #[test]
+    pub fn test_keys() {
+        let mut grown = BitSet::<usize>::with_capacity(500);
+        grown.extend([1, 6]);
+        let small = BitSet::<usize, [u8; 1]>::from(0b1000010u64);
+        assert_eq!(small, BitSet::<usize, [u8; 1]>::from(0b1000010u64));
+        assert_eq!(grown, BitSet::<usize>::from(0b1000010u64));
+        assert_eq!(u64::try_from(&grown), Ok(0b1000010));
+        grown.insert(200);
+        assert_eq!(u128::try_from(&grown), Err(()));
+
+        let hash = |set: &BitSet| {
+            let mut hasher = DefaultHasher::new();
+            set.hash(&mut hasher);
+            hasher.finish()
+        };
+        let (a, b) = (BitSet::from(0b101u64), BitSet::<usize>::from(0b110u128));
+        assert_ne!(hash(&a), hash(&b));
+        assert_eq!(hash(&a), hash(&[0, 2].into_iter().collect()));
+        assert!(a < b && b < grown);
+
+        let key = PackedKey::default().push_set(&BitSet::<usize>::with_max(3)).and_then(|k| k.push(5, 3));
+        assert_eq!(key.map(|k| k.bits()), Some(5 << 4));
+        assert_eq!(PackedKey::default().push(8, 3), None);
+        assert_eq!(PackedKey::default().push(1, 100).and_then(|k| k.push(1, 29)), None);
+    }
+
+    #[test]
+    pub fn test_fixed_store_with_bigger_set() {
+        let mut small = BitSet::<usize, [u64; 1]>::with_capacity(10);
+        small.insert(1);
+        let big = BitSet::<usize>::from_iter([3, 40]) | BitSet::<usize>::with_capacity(300);
+        small |= &big;
+        assert!(small.members().eq([1, 3, 40]));
+        assert_eq!(small.universe(), 64);
+        assert_eq!((!small).len(), 61);
+        small ^= &big;
+        assert!(small.members().eq([1]));
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn test_fixed_store_overflow() {
+        let mut small = BitSet::<usize, [u64; 1]>::with_capacity(10);
+        small |= BitSet::<usize>::from_iter([3, 100]);
+    }
+
+    #[test]
+    pub fn test_complement_depends_on_universe() {
+        let narrow = BitSet::<usize>::from_iter([1, 2]);
+        let mut wide = BitSet::<usize>::with_capacity(10);
+        wide.extend([1, 2]);
+        assert_eq!(narrow, wide);
+        assert_eq!((narrow.universe(), wide.universe()), (3, 10));
+        assert!((!&narrow).members().eq([0]));
+        assert!((!&wide).members().eq([0, 3, 4, 5, 6, 7, 8, 9]));
+        assert_ne!(!narrow, !wide);
+    }
 }
@@ -0,0 +1,176 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    hash::Hash,
+    ops::{Add, Mul, Sub},
+};
+
+/// Where a sequence of states repeats: from step `prefix` on, every state is the same as the one `period` steps before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub prefix: usize,
+    pub period: usize,
+}
+impl Cycle {
+    /// The first step with the same state as step `n`
+    pub fn equivalent_step(&self, n: usize) -> usize {
+        if n < self.prefix {
+            n
+        } else {
+            self.prefix + (n - self.prefix) % self.period
+        }
+    }
+    /// The value at step `n`, given the values of every step up to `prefix + period` and assuming each cycle adds the same amount.
+    /// `None` if there aren't enough values or the result doesn't fit in `V`
+    pub fn extrapolate<V>(&self, values: &[V], n: usize) -> Option<V>
+    where
+        V: Copy + Add<Output = V> + Sub<Output = V> + Mul<Output = V> + TryFrom<usize>,
+    {
+        if let Some(&value) = values.get(n) {
+            return Some(value);
+        }
+        if n < self.prefix {
+            return None;
+        }
+        let per_cycle = *values.get(self.prefix + self.period)? - values[self.prefix];
+        let cycles = V::try_from((n - self.prefix) / self.period).ok()?;
+        Some(values[self.equivalent_step(n)] + cycles * per_cycle)
+    }
+}
+
+/// Floyd's tortoise and hare over a pure `step` function, comparing states by `key`.
+/// Only keeps two states at a time, but never returns if the states don't repeat
+#[allow(dead_code)]
+pub fn floyd<S, K: Eq>(initial: S, step: impl Fn(&S) -> S, key: impl Fn(&S) -> K) -> Cycle
+where
+    S: Clone,
+{
+    // The hare moves twice as fast, so they meet somewhere in the cycle
+    let mut tortoise = step(&initial);
+    let mut hare = step(&step(&initial));
+    while key(&tortoise) != key(&hare) {
+        tortoise = step(&tortoise);
+        hare = step(&step(&hare));
+    }
+    // The meeting point is a whole number of cycles from the start, so walking in step from there and from the start meets at the cycle's start
+    let mut prefix = 0;
+    tortoise = initial;
+    while key(&tortoise) != key(&hare) {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix += 1;
+    }
+    let mut period = 1;
+    hare = step(&tortoise);
+    while key(&tortoise) != key(&hare) {
+        hare = step(&hare);
+        period += 1;
+    }
+    Cycle { prefix, period }
+}
+
+/// Brent's algorithm over a pure `step` function, comparing states by `key`.
+/// Takes fewer steps than [`floyd`], but likewise never returns if the states don't repeat
+#[allow(dead_code)]
+pub fn brent<S, K: Eq>(initial: S, step: impl Fn(&S) -> S, key: impl Fn(&S) -> K) -> Cycle
+where
+    S: Clone,
+{
+    // The tortoise teleports to the hare at every power of two, until the hare comes back round to it
+    let (mut power, mut period) = (1, 1);
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while key(&tortoise) != key(&hare) {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+    }
+    // Then with the hare a period ahead, they meet at the cycle's start
+    tortoise = initial.clone();
+    hare = initial;
+    for _ in 0..period {
+        hare = step(&hare);
+    }
+    let mut prefix = 0;
+    while key(&tortoise) != key(&hare) {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix += 1;
+    }
+    Cycle { prefix, period }
+}
+
+/// Steps `state` in place until its fingerprint repeats, for states that are too big to clone or step twice.
+/// Also returns the `value` of every step up to and including the repeat, ready for [`Cycle::extrapolate`].
+/// `None` if nothing repeats within `max_steps`
+pub fn find_cycle<S, K: Hash + Eq, V>(
+    state: &mut S,
+    max_steps: usize,
+    mut step: impl FnMut(&mut S),
+    mut fingerprint: impl FnMut(&S) -> K,
+    mut value: impl FnMut(&S) -> V,
+) -> Option<(Cycle, Vec<V>)> {
+    let mut seen = HashMap::new();
+    let mut values = Vec::new();
+    for n in 0..=max_steps {
+        values.push(value(state));
+        match seen.entry(fingerprint(state)) {
+            Entry::Occupied(first) => {
+                let prefix = *first.get();
+                return Some((Cycle { prefix, period: n - prefix }, values));
+            }
+            Entry::Vacant(v) => {
+                v.insert(n);
+            }
+        }
+        step(state);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{brent, find_cycle, floyd, Cycle};
+
+    #[test]
+    fn detectors_agree() {
+        // 3, 10, 101, 2, 5, 26, 167, 95, 101, ...
+        let step = |&x: &u64| (x * x + 1) % 255;
+        let expected = Cycle { prefix: 2, period: 6 };
+        assert_eq!(floyd(3, step, |&x| x), expected);
+        assert_eq!(brent(3, step, |&x| x), expected);
+
+        let mut state = 3;
+        let (cycle, values) = find_cycle(&mut state, 100, |x| *x = step(x), |&x| x, |&x| x).unwrap();
+        assert_eq!(cycle, expected);
+        assert_eq!(values.len(), 9);
+        assert_eq!(cycle.equivalent_step(1_000_000_000_000), 4);
+        assert_eq!(find_cycle(&mut 0u64, 100, |x| *x += 1, |&x| x, |&x| x), None);
+    }
+
+    #[test]
+    fn extrapolate() {
+        // Steps of 1, 2, 1, 2, ... after a start of 10
+        let mut state = (0usize, 0usize);
+        let (cycle, heights) = find_cycle(
+            &mut state,
+            10,
+            |(n, height)| {
+                *height += if *n == 0 { 10 } else { 1 + (*n + 1) % 2 };
+                *n += 1;
+            },
+            |&(n, _)| n.min(1) + n % 2 * 2,
+            |&(_, height)| height,
+        )
+        .unwrap();
+        assert_eq!(cycle, Cycle { prefix: 1, period: 2 });
+        assert_eq!(heights, [0, 10, 11, 13]);
+        assert_eq!(cycle.extrapolate(&heights, 5), Some(10 + 3 + 3));
+        assert_eq!(cycle.extrapolate(&heights, 6), Some(10 + 3 + 3 + 1));
+        assert_eq!(cycle.extrapolate(&heights, 1_000_000_000_001), Some(10 + 3 * 500_000_000_000));
+        assert_eq!(Cycle { prefix: 5, period: 2 }.extrapolate(&heights, 4), None);
+    }
+}
@@ -1,6 +1,6 @@
 use std::str::FromStr;
 
-use crate::{solution::AOCSolution, bitset::{BitSet, DefaultedBytes}};
+use crate::{solution::AOCSolution, bitset::BitSet};
 
 #[derive(Debug,Clone)]
 pub enum Instruction {
@@ -65,7 +65,7 @@ impl <I:Iterator<Item=Instruction>>Iterator for ElfCPUCycles<'_,I>{
 pub struct ElfCPUState {
     x:i16,
     pixel_index:u8,
-    pixels:BitSet<u8,DefaultedBytes<30>>
+    pixels:BitSet<u8,[u64;4]>
 }
 impl ElfCPUState {
     pub fn set_next_pixel(&mut self){
@@ -1,6 +1,6 @@
-use std::{str::FromStr, collections::VecDeque, num::ParseIntError, cmp::Reverse};
+use std::{str::FromStr, collections::VecDeque, num::ParseIntError, cmp::Reverse, fmt::{Display, Write}};
 
-use crate::solution::AOCSolution;
+use crate::{solution::AOCSolution, matcher::{Matcher, MatcherOutput, MatcherError, FromStrMatcher, Delimeted, Printer}, cycle::find_cycle};
 
 type Worry = u128;
 
@@ -24,6 +24,16 @@ impl FromStr for Operation {
         }
     }
 }
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Add=>"+",
+            Self::Sub=>"-",
+            Self::Mult=>"*",
+            Self::Div=>"/",
+        })
+    }
+}
 
 #[derive(Debug,Clone,Copy,Eq,PartialEq)]
 pub enum OpPart {
@@ -40,7 +50,15 @@ impl FromStr for OpPart {
         }
     }
 }
-#[derive(Debug,Clone)]
+impl Display for OpPart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Old=>f.write_str("old"),
+            Self::Const(w)=>write!(f,"{w}"),
+        }
+    }
+}
+#[derive(Debug,Clone,PartialEq,Eq)]
 pub struct Monkey {
     items:VecDeque<Worry>,
     operation: (OpPart,Operation,OpPart),
@@ -51,7 +69,10 @@ pub struct Monkey {
 impl Monkey {
     pub fn handle_next<F:Fn(Worry)->Worry>(&mut self,worry_hanlder:F)->Option<(usize,Worry)>{
         let worry = self.items.pop_front()?;
-
+        Some(self.inspect(worry,worry_hanlder))
+    }
+    /// Where an item with this worry gets thrown, and its worry once it lands
+    pub fn inspect<F:Fn(Worry)->Worry>(&self,worry:Worry,worry_hanlder:F)->(usize,Worry){
         fn eval(part:&OpPart,curr: Worry)-> Worry {
             match part {
                 &OpPart::Const(w)=>w,
@@ -72,50 +93,156 @@ impl Monkey {
         }else{
             self.false_throw
         };
-        Some((dest,worry_after_inspection))
+        (dest,worry_after_inspection)
     }
 }
-impl FromStr for Monkey {
-    type Err=();
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines();
-        lines.next();
-        let starting_items = lines.next()
-            .and_then(|l|l.split_once(": "))
-            .ok_or(())
-            .and_then(|(_,nums)|{
-                nums.split(", ").map(Worry::from_str).collect::<Result<_,_>>().map_err(|_|{})
-            })?;
-            let operation = lines.next()
-        .and_then(|l|{
-            let (_,rhs) = l.split_once("= ")?;
-            let mut lines = rhs.split_whitespace();
-            let left = OpPart::from_str(lines.next()?).ok()?;
-            let op = Operation::from_str(lines.next()?).ok()?;
-            let right = OpPart::from_str(lines.next()?).ok()?;
-            Some((left,op,right))
-        })
-        .ok_or(())?;
-        let test_line = lines.next().ok_or(())?.split_whitespace().last().ok_or(())?.parse().map_err(|_|{})?;
-        let true_line = lines.next().ok_or(())?.split_whitespace().last().ok_or(())?.parse().map_err(|_|{})?;
-        let false_line = lines.next().ok_or(())?.split_whitespace().last().ok_or(())?.parse().map_err(|_|{})?;
-
-        Ok(Self {
-            items:starting_items,
-            operation,
-            divisibility_rule:test_line,
-            true_throw:true_line,
-            false_throw:false_line
+
+/// Follows one item through a round from `monkey`, calling `inspected` with each monkey that handles it.
+/// Items thrown to a later monkey are handled again in the same round, so it ends with a throw back to an earlier one
+fn item_round<F:Fn(Worry)->Worry>(monkeys:&[Monkey],(mut monkey,mut worry):(usize,Worry),worry_hanlder:F,mut inspected:impl FnMut(usize))->(usize,Worry){
+    loop {
+        inspected(monkey);
+        let (dest,new_worry) = monkeys[monkey].inspect(worry,&worry_hanlder);
+        if dest <= monkey {
+            return (dest,new_worry);
+        }
+        (monkey,worry) = (dest,new_worry);
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum MonkeyParseErr {
+    /// The named part of a monkey block didn't match
+    Expected(&'static str),
+    /// Dividing by zero in the operation or test
+    ZeroDivisor,
+    /// Monkey blocks must be labelled 0, 1, 2... in order
+    WrongId { expected:usize, found:usize },
+    /// A throw to a monkey that isn't in the troop
+    ThrowOutOfRange { target:usize, monkey_count:usize },
+}
+
+/// Matches a single monkey block, along with the id in its header
+/// and where its true and false throw targets start, so they can be checked once the whole troop is known
+#[derive(Debug,Default,Clone,Copy)]
+pub struct MonkeyMatcher;
+impl Matcher for MonkeyMatcher {
+    type Match<'m> = (usize,Monkey,[usize;2]);
+    type Error<'e> = MonkeyParseErr;
+    fn next_match<'s>(
+        &mut self,
+        source: &'s str,
+    ) -> Result<MatcherOutput<'s, Self::Match<'s>>, MatcherError<Self::Error<'s>>> {
+        let mut remaining = source;
+        let mut chars_consumed = 0;
+        macro_rules! expect {
+            ($part:literal, $matcher:expr) => {{
+                let mut matcher = $matcher;
+                let output = matcher.next_match(remaining)
+                    .map_err(|e|MatcherError::new(chars_consumed+e.index, MonkeyParseErr::Expected($part)))?;
+                remaining = output.remaining;
+                chars_consumed += output.chars_consumed;
+                output.matched
+            }};
+        }
+        expect!("monkey header", "Monkey ");
+        let id = expect!("monkey id", FromStrMatcher::<usize>::MATCHER);
+        expect!("starting items", ":\n  Starting items: ");
+        let items = expect!("item worry levels", Delimeted::<VecDeque<Worry>,_,_>::new(FromStrMatcher::<Worry>::MATCHER, ", "));
+        expect!("operation", "\n  Operation: new = ");
+        let left = expect!("operation's left operand", FromStrMatcher::<OpPart>::MATCHER);
+        expect!("space", ' ');
+        let operation_start = chars_consumed;
+        let operation = expect!("operator", FromStrMatcher::<Operation>::MATCHER);
+        expect!("space", ' ');
+        let right = expect!("operation's right operand", FromStrMatcher::<OpPart>::MATCHER);
+        if (operation, right) == (Operation::Div, OpPart::Const(0)) {
+            return Err(MatcherError::new(operation_start, MonkeyParseErr::ZeroDivisor));
+        }
+        expect!("test", "\n  Test: divisible by ");
+        let divisor_start = chars_consumed;
+        let divisibility_rule = expect!("test divisor", FromStrMatcher::<Worry>::MATCHER);
+        if divisibility_rule == 0 {
+            return Err(MatcherError::new(divisor_start, MonkeyParseErr::ZeroDivisor));
+        }
+        expect!("true branch", "\n    If true: throw to monkey ");
+        let true_throw_start = chars_consumed;
+        let true_throw = expect!("true branch target", FromStrMatcher::<usize>::MATCHER);
+        expect!("false branch", "\n    If false: throw to monkey ");
+        let false_throw_start = chars_consumed;
+        let false_throw = expect!("false branch target", FromStrMatcher::<usize>::MATCHER);
+
+        Ok(MatcherOutput {
+            matched: (id,Monkey {
+                items,
+                operation:(left,operation,right),
+                divisibility_rule,
+                true_throw,
+                false_throw,
+            },[true_throw_start,false_throw_start]),
+            remaining,
+            chars_consumed,
         })
     }
 }
+impl Printer<(usize,&Monkey)> for MonkeyMatcher {
+    fn print<W: Write>(&self, &(id,monkey): &(usize,&Monkey), out: &mut W) -> std::fmt::Result {
+        let items = Delimeted::<VecDeque<Worry>,_,_>::new(FromStrMatcher::<Worry>::MATCHER, ", ");
+        let (left,operation,right) = monkey.operation;
+        write!(out, "Monkey {id}:\n  Starting items: ")?;
+        items.print(&monkey.items, out)?;
+        write!(out, "\n  Operation: new = {left} {operation} {right}")?;
+        write!(out, "\n  Test: divisible by {}", monkey.divisibility_rule)?;
+        write!(out, "\n    If true: throw to monkey {}", monkey.true_throw)?;
+        write!(out, "\n    If false: throw to monkey {}", monkey.false_throw)
+    }
+}
 
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,PartialEq,Eq)]
 pub struct Monkeys(Vec<Monkey>);
 impl FromStr for Monkeys {
-    type Err=();
+    type Err=MatcherError<MonkeyParseErr>;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self(s.split("\n\n").map(Monkey::from_str).collect::<Result<_,_>>()?))
+        let mut monkeys = Vec::new();
+        let mut target_starts = Vec::new();
+        let mut remaining = s.trim_end();
+        let mut chars_consumed = 0;
+        loop {
+            let block = MonkeyMatcher.next_match(remaining).map_err(|e|e.with_offset(chars_consumed))?;
+            let (id,monkey,block_target_starts) = block.matched;
+            if id != monkeys.len() {
+                return Err(MatcherError::new(chars_consumed, MonkeyParseErr::WrongId { expected: monkeys.len(), found: id }));
+            }
+            target_starts.push(block_target_starts.map(|start|chars_consumed+start));
+            monkeys.push(monkey);
+            chars_consumed += block.chars_consumed;
+            if block.remaining.is_empty() {
+                break;
+            }
+            let separator = "\n\n".next_match(block.remaining)
+                .map_err(|e|MatcherError::new(chars_consumed+e.index, MonkeyParseErr::Expected("blank line between monkeys")))?;
+            chars_consumed += separator.chars_consumed;
+            remaining = separator.remaining;
+        }
+        let monkey_count = monkeys.len();
+        for (monkey,target_starts) in monkeys.iter().zip(target_starts) {
+            let mut targets = [monkey.true_throw,monkey.false_throw].into_iter().zip(target_starts);
+            if let Some((target,target_start)) = targets.find(|&(target,_)|target >= monkey_count) {
+                return Err(MatcherError::new(target_start, MonkeyParseErr::ThrowOutOfRange { target, monkey_count }));
+            }
+        }
+        Ok(Self(monkeys))
+    }
+}
+impl Display for Monkeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (id,monkey) in self.0.iter().enumerate() {
+            if id != 0 {
+                f.write_str("\n\n")?;
+            }
+            MonkeyMatcher.print(&(id,monkey), f)?;
+        }
+        Ok(())
     }
 }
 
@@ -125,7 +252,7 @@ impl AOCSolution for Solution {
     type Part1=u128;
     type Part2=u128;
     type Err = ();
-    fn solve(Monkeys(mut monkeys) :Self::Input)->Result<(Self::Part1,Self::Part2),()> {
+    fn solve(Monkeys(monkeys) :Self::Input)->Result<(Self::Part1,Self::Part2),()> {
         let mut simple_monkeys = monkeys.clone();
         let mut inspection_counts = vec![0;simple_monkeys.len()];
         let mut incoming_throws = vec![VecDeque::<Worry>::new();monkeys.len()];
@@ -148,16 +275,25 @@ impl AOCSolution for Solution {
 
         let worry_div_prod = monkeys.iter().map(|m|m.divisibility_rule).product::<Worry>();
 
+        // Items never affect each other, and each one's round soon repeats, so only one cycle per item needs simulating
+        const ROUNDS:usize = 10000;
         let mut inspection_counts = vec![0;monkeys.len()];
-        let mut incoming_throws = vec![VecDeque::<Worry>::new();monkeys.len()];
-        for _ in 0..10000 {
-            for monkey_index in 0..monkeys.len() {
-                let count = &mut inspection_counts[monkey_index];
-                let monkey = &mut monkeys[monkey_index];
-                monkey.items.extend(incoming_throws[monkey_index].drain(..));
-                while let Some((dest,w)) = monkey.handle_next(|w|w%worry_div_prod) {
-                    *count+=1;
-                    incoming_throws[dest].push_back(w);
+        for (start,monkey) in monkeys.iter().enumerate() {
+            for &worry in &monkey.items {
+                // The item, and how many times each monkey has inspected it so far
+                let mut state = ((start,worry),vec![0;monkeys.len()]);
+                let step = |(item,counts):&mut ((usize,Worry),Vec<u128>)|{
+                    *item = item_round(&monkeys,*item,|w|w%worry_div_prod,|m|counts[m]+=1);
+                };
+                match find_cycle(&mut state,ROUNDS-1,step,|&(item,_)|item,|(_,counts)|counts.clone()) {
+                    Some((cycle,counts_after)) => for (monkey,count) in inspection_counts.iter_mut().enumerate() {
+                        let monkey_counts:Vec<_> = counts_after.iter().map(|counts|counts[monkey]).collect();
+                        *count += cycle.extrapolate(&monkey_counts,ROUNDS).ok_or(())?;
+                    },
+                    // Nothing repeated, but that took every round anyway
+                    None => for (count,item_count) in inspection_counts.iter_mut().zip(state.1) {
+                        *count += item_count;
+                    },
                 }
             }
         }
@@ -167,4 +303,67 @@ impl AOCSolution for Solution {
 
         Ok((simple_mb,tough_mb))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Monkeys, MonkeyParseErr, Solution};
+    use crate::solution::AOCSolution;
+
+    const EXAMPLE:&str = "Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+";
+
+    #[test]
+    fn example() {
+        let monkeys:Monkeys = EXAMPLE.parse().unwrap();
+        assert_eq!(monkeys.to_string(), EXAMPLE.trim_end());
+        assert_eq!(Solution::solve(monkeys), Ok((10605,2713310158)));
+    }
+
+    #[test]
+    fn errors() {
+        let bad_operator = EXAMPLE.replacen("old * 19", "old ^ 19", 1);
+        let err = bad_operator.parse::<Monkeys>().unwrap_err();
+        assert_eq!(err.error, MonkeyParseErr::Expected("operator"));
+        assert_eq!(&bad_operator[err.index..err.index+4], "^ 19");
+
+        let swapped = EXAMPLE.replacen("Monkey 1:", "Monkey 7:", 1);
+        let err = swapped.parse::<Monkeys>().unwrap_err();
+        assert_eq!(err.error, MonkeyParseErr::WrongId { expected: 1, found: 7 });
+        assert!(swapped[err.index..].starts_with("Monkey 7:"));
+
+        let out_of_range = EXAMPLE.replacen("throw to monkey 3", "throw to monkey 4", 1);
+        let err = out_of_range.parse::<Monkeys>().unwrap_err();
+        assert_eq!(err.error, MonkeyParseErr::ThrowOutOfRange { target: 4, monkey_count: 4 });
+        assert_eq!(err.index, out_of_range.find("monkey 4").unwrap() + "monkey ".len());
+
+        let zero = EXAMPLE.replacen("divisible by 13", "divisible by 0", 1);
+        assert_eq!(zero.parse::<Monkeys>().unwrap_err().error, MonkeyParseErr::ZeroDivisor);
+    }
+}
@@ -1,6 +1,6 @@
-use std::str::FromStr;
+use std::{str::FromStr, fmt::Display};
 
-use crate::{solution::{AOCSolution, Labeled}, grid::Grid, astar::a_star};
+use crate::{solution::{AOCSolution, Labeled}, grid::{Grid, GridParseErr}, graph::bfs_to_all};
 
 #[derive(Debug,Clone,Copy,PartialEq, Eq)]
 pub enum MapSpot {
@@ -38,20 +38,25 @@ impl From<MapSpot> for u8 {
 #[derive(Debug,Clone)]
 pub struct HeightMap(Grid<MapSpot>);
 impl FromStr for HeightMap {
-    type Err=();
+    type Err=GridParseErr<char>;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let side_len = s.lines().next().ok_or(())?.len();
-        let heights = s.chars().filter(|c|!c.is_whitespace()).map(|c|{
+        Grid::parse_chars(s, |c|{
             match c {
-                'a'..='z'=>{
-                    Some(MapSpot::Height((c as u8)-b'a'))
-                }
-                'S'=>Some(MapSpot::Start),
-                'E'=>Some(MapSpot::End),
-                _=>None
+                'a'..='z'=>Ok(MapSpot::Height((c as u8)-b'a')),
+                'S'=>Ok(MapSpot::Start),
+                'E'=>Ok(MapSpot::End),
+                _=>Err(c)
             }
-        }).flatten().collect::<Vec<_>>();
-        Ok(HeightMap(Grid::from_parts(heights,side_len).ok_or(())?))
+        }).map(HeightMap)
+    }
+}
+impl Display for HeightMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.display(|spot|match spot {
+            MapSpot::Start=>'S',
+            MapSpot::Height(h)=>(b'a'+h) as char,
+            MapSpot::End=>'E',
+        }).fmt(f)
     }
 }
 
@@ -69,47 +74,44 @@ impl AOCSolution for Solution {
 
         // dbg!(start,end);
 
-        let path = a_star([start], |(x,y)|{
-            let current_map_spot = ref_input.get((x.clone(),y.clone()));
-            current_map_spot.map(|spot|[
-                x.checked_sub(1).map(|x|(x,y)),
-                y.checked_sub(1).map(|y|(x,y)),
-                x.checked_add(1).map(|x|(x,y)),
-                y.checked_add(1).map(|y|(x,y)),
-            ].into_iter().flatten().filter(move |s|{
-                if let Some(s) = ref_input.get(*s) {
-                    spot.can_climb_to(s)
-                }else{
-                    false
-                }
-            }).map(|s|(s,1))).into_iter().flatten()
-        }, |s|{
-            s.0.abs_diff(end.0) + s.1.abs_diff(end.1)
-        },  |s|s==&end);
-
-        let scenic_path = a_star(input.indices().filter_map(|(s,_)|(matches!(ref_input[s],MapSpot::Start|MapSpot::Height(0)).then_some(s))), |(x,y)|{
-            let current_map_spot = ref_input.get((x.clone(),y.clone()));
-            current_map_spot.map(|spot|[
-                x.checked_sub(1).map(|x|(x,y)),
-                y.checked_sub(1).map(|y|(x,y)),
-                x.checked_add(1).map(|x|(x,y)),
-                y.checked_add(1).map(|y|(x,y)),
-            ].into_iter().flatten().filter(move |s|{
-                if let Some(s) = ref_input.get(*s) {
-                    spot.can_climb_to(s)
-                }else{
-                    false
-                }
-            }).map(|s|(s,1))).into_iter().flatten()
-        }, |s|{
-            s.0.abs_diff(end.0) + s.1.abs_diff(end.1)
-        },  |s|s==&end);
-
-        dbg!(path == scenic_path);
+        // Search backwards from the end, so every possible start is found in one pass
+        let possible_starts = input.indices()
+            .map(|(pos,_)|pos)
+            .filter(|&pos|matches!(ref_input[pos],MapSpot::Start|MapSpot::Height(0)));
+        let distances_to_end = bfs_to_all([end], |pos:(usize,usize)|{
+            let spot = ref_input[pos];
+            ref_input.neighbors4(pos)
+                .filter(move |(_,neighbor)|neighbor.can_climb_to(&spot))
+                .map(|(neighbor,_)|neighbor.into())
+        }, possible_starts);
 
         Ok((
-            (path.ok_or(())?.len()-1,"steps").into(),
-            (scenic_path.ok_or(())?.len()-1,"steps").into(),
+            (*distances_to_end.get(&start).ok_or(())?,"steps").into(),
+            (*distances_to_end.values().min().ok_or(())?,"steps").into(),
         ))
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use super::{HeightMap, MapSpot};
+    use crate::{astar::a_star, graph::bfs_to};
+
+    #[test]
+    fn a_star_matches_bfs() {
+        let HeightMap(map) = "Sabqponm
+abcryxxl
+accszExk
+acctuvwj
+abdefghi".parse().unwrap();
+        let find = |target| map.indices().find_map(|(pos,_)|(map[pos] == target).then_some(pos)).unwrap();
+        let (start,end) = (find(MapSpot::Start),find(MapSpot::End));
+        let climbable = |pos:(usize,usize)|{
+            let spot = map[pos];
+            map.neighbors4(pos).filter(move |(_,neighbor)|spot.can_climb_to(neighbor)).map(|(neighbor,_)|neighbor.into())
+        };
+
+        let result = a_star([start], |pos|climbable(pos).map(|neighbor|(neighbor,1usize)), |&(x,y)|x.abs_diff(end.0)+y.abs_diff(end.1), |&pos|pos == end).unwrap();
+        assert_eq!(result.cost, 31);
+        assert_eq!(Some(result.cost), bfs_to([start], climbable, |&pos|pos == end).map(|result|result.cost));
+    }
+}
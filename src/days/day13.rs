@@ -1,6 +1,6 @@
-use std::str::FromStr;
+use std::{str::FromStr, fmt::{self, Write}};
 
-use crate::{solution::AOCSolution, matcher::{MatchNestedList, FromStrMatcher, ShortMatchNestedListErr}};
+use crate::{solution::AOCSolution, matcher::{MatchNestedList, FromStrMatcher, ShortMatchNestedListErr, NestedList}, or::Or};
 
 type Value = u16;
 
@@ -9,53 +9,127 @@ type Value = u16;
 //     ExpectedOpenBracket(char)
 // }
 
-#[derive(Debug,Clone,PartialEq,Eq)]
+#[derive(Eq)]
 pub enum PacketListItem {
     Value(Value),
-    List(Vec<PacketListItem>)
+    List(PacketList)
+}
+impl PartialEq for PacketListItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+impl Clone for PacketListItem {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Value(value) => Self::Value(*value),
+            Self::List(list) => Self::List(list.clone()),
+        }
+    }
+}
+impl fmt::Debug for PacketListItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Value(value) => write!(f, "{value}"),
+            Self::List(list) => list.fmt(f),
+        }
+    }
 }
 impl PartialOrd for PacketListItem {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
+
+/// Walks the entries of a list, or a lone value as if it were a list containing just it
+enum Entries<'a> {
+    List(std::slice::Iter<'a,PacketListItem>),
+    Single(Option<&'a PacketListItem>),
+}
+impl <'a> Iterator for Entries<'a> {
+    type Item = &'a PacketListItem;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Entries::List(iter) => iter.next(),
+            Entries::Single(item) => item.take(),
+        }
+    }
+}
 impl Ord for PacketListItem {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        match (self,other) {
-            (Self::Value(a),Self::Value(b))=>a.cmp(b),
-
-            (Self::Value(_),Self::List(list))=>{
-                let first = list.get(0);
-                if let Some(first) = first {
-                    self.cmp(first).then(if list.len()>2 {
-                        std::cmp::Ordering::Less
-                    }else{
-                        std::cmp::Ordering::Equal
-                    })
-                }else{
-                    std::cmp::Ordering::Greater
-                }
-            },
-            (Self::List(list),Self::Value(_))=>{
-                let first = list.get(0);
-                if let Some(first) = first {
-                    first.cmp(other).then(if list.len()>2 {
-                        std::cmp::Ordering::Greater
-                    }else{
-                        std::cmp::Ordering::Equal
-                    })
-                }else{
-                    std::cmp::Ordering::Less
+        use std::cmp::Ordering;
+        // An explicit stack of the lists being compared, so deep packets can't overflow the call stack
+        let mut stack = vec![(Entries::Single(Some(self)),Entries::Single(Some(other)))];
+        while let Some((a,b)) = stack.last_mut() {
+            match (a.next(),b.next()) {
+                (None,None) => {
+                    stack.pop();
+                },
+                (None,Some(_)) => return Ordering::Less,
+                (Some(_),None) => return Ordering::Greater,
+                (Some(a),Some(b)) => match (a,b) {
+                    (Self::Value(a_value),Self::Value(b_value)) => {
+                        let value_ordering = a_value.cmp(b_value);
+                        if value_ordering.is_ne() {
+                            return value_ordering;
+                        }
+                    },
+                    (Self::Value(_),Self::List(b_list)) => stack.push((Entries::Single(Some(a)),Entries::List(b_list.0.iter()))),
+                    (Self::List(a_list),Self::Value(_)) => stack.push((Entries::List(a_list.0.iter()),Entries::Single(Some(b)))),
+                    (Self::List(a_list),Self::List(b_list)) => stack.push((Entries::List(a_list.0.iter()),Entries::List(b_list.0.iter()))),
                 }
-            },
-
-            (Self::List(a),Self::List(b))=>a.cmp(b),
-
+            }
         }
+        Ordering::Equal
     }
 }
-#[derive(Default,Debug,Clone,PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Default,PartialEq, Eq, PartialOrd, Ord)]
 pub struct PacketList(Vec<PacketListItem>);
+impl Clone for PacketList {
+    // Copies with an explicit stack of the lists being copied, each with its copy so far
+    fn clone(&self) -> Self {
+        let mut stack = vec![(self.0.iter(),Vec::with_capacity(self.0.len()))];
+        loop {
+            let (source,copy) = stack.last_mut().expect("the outermost list is only popped to return");
+            match source.next() {
+                Some(PacketListItem::Value(value)) => copy.push(PacketListItem::Value(*value)),
+                Some(PacketListItem::List(list)) => stack.push((list.0.iter(),Vec::with_capacity(list.0.len()))),
+                None => {
+                    let (_,copy) = stack.pop().expect("just looked at it");
+                    match stack.last_mut() {
+                        Some((_,parent)) => parent.push(PacketListItem::List(PacketList(copy))),
+                        None => return PacketList(copy),
+                    }
+                }
+            }
+        }
+    }
+}
+/// Prints in the input's notation, walking an explicit stack like [`Ord`] does
+impl fmt::Debug for PacketList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut stack = vec![(self.0.iter(),true)];
+        f.write_char('[')?;
+        while let Some((entries,first)) = stack.last_mut() {
+            let Some(item) = entries.next() else {
+                stack.pop();
+                f.write_char(']')?;
+                continue;
+            };
+            if !std::mem::replace(first,false) {
+                f.write_char(',')?;
+            }
+            match item {
+                PacketListItem::Value(value) => write!(f,"{value}")?,
+                PacketListItem::List(list) => {
+                    f.write_char('[')?;
+                    stack.push((list.0.iter(),true));
+                },
+            }
+        }
+        Ok(())
+    }
+}
 
 impl Extend<Value> for PacketList {
     fn extend<T: IntoIterator<Item = Value>>(&mut self, iter: T) {
@@ -64,9 +138,35 @@ impl Extend<Value> for PacketList {
 }
 impl Extend<PacketList> for PacketList {
     fn extend<T: IntoIterator<Item = PacketList>>(&mut self, iter: T) {
-        self.0.extend(iter.into_iter().map(|l|PacketListItem::List(l.0)))
+        self.0.extend(iter.into_iter().map(PacketListItem::List))
     }
 }
+impl Drop for PacketList {
+    // Flatten the nested lists before dropping them, rather than recursing once per level
+    fn drop(&mut self) {
+        let mut items = std::mem::take(&mut self.0);
+        while let Some(item) = items.pop() {
+            if let PacketListItem::List(mut list) = item {
+                items.append(&mut list.0);
+            }
+        }
+    }
+}
+impl NestedList for PacketList {
+    type Item = Value;
+    fn entries(&self) -> impl Iterator<Item = Or<&Self::Item, &Self>> {
+        self.0.iter().map(|item|match item {
+            PacketListItem::Value(v)=>Or::Left(v),
+            PacketListItem::List(l)=>Or::Right(l),
+        })
+    }
+}
+
+const MAX_PACKET_DEPTH:usize = 256;
+type PacketGrammar = MatchNestedList<PacketList, FromStrMatcher<Value>, char, char, char>;
+fn packet_grammar()->PacketGrammar {
+    MatchNestedList::new('[', ']', ',', FromStrMatcher::<Value>::MATCHER).with_max_depth(MAX_PACKET_DEPTH)
+}
 
 #[derive(Debug,Clone)]
 pub struct PacketPairs(Vec<(PacketList,PacketList)>);
@@ -74,18 +174,12 @@ impl FromStr for PacketPairs {
     type Err= ShortMatchNestedListErr;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use crate::matcher::Matcher;
-        let pairs = s.split("\n\n").flat_map(|l|l.split_once("\n")).map(|(left,right)| {
-            let mut matcher = MatchNestedList::<PacketList, FromStrMatcher<u16>, char, char, char>::new('[', ']', ',', FromStrMatcher::<Value>::MATCHER);
-            // matcher.next_match(left).and_then(|l|matcher.next_match(right).map(|r|(l.matched,r.matched)))
-            //     .expect(left)
-        (
-            matcher.next_match(left).expect(left).matched,
-            matcher.next_match(right).expect(right).matched
-        )
-
-            // .map_err(|e|e.map(ShortMatchNestedListErr::from))
-            // }).collect::<Result<Vec<_>,_>>()?;
-            }).collect::<Vec<_>>();
+        let mut matcher = packet_grammar();
+        let pairs = s.split("\n\n").flat_map(|l|l.split_once('\n')).map(|(left,right)| {
+            let left = matcher.next_match(left).map_err(|e|ShortMatchNestedListErr::from(e.error))?.matched;
+            let right = matcher.next_match(right).map_err(|e|ShortMatchNestedListErr::from(e.error))?.matched;
+            Ok((left,right))
+        }).collect::<Result<Vec<_>,ShortMatchNestedListErr>>()?;
     Ok(Self(pairs))
     }
 }
@@ -108,8 +202,8 @@ impl AOCSolution for Solution {
         .sum();
 
         let distress_packets = [
-            (1,PacketList(vec![PacketListItem::List(vec![PacketListItem::Value(2)])])),
-            (2,PacketList(vec![PacketListItem::List(vec![PacketListItem::Value(6)])])),
+            (1,PacketList(vec![PacketListItem::List(PacketList(vec![PacketListItem::Value(2)]))])),
+            (2,PacketList(vec![PacketListItem::List(PacketList(vec![PacketListItem::Value(6)]))])),
         ];
         let distress_packet_indices = distress_packets.map(|(offset,p)|{
             offset+input.iter().flat_map(|(a,b)|[a,b]).filter_map(|a|{
@@ -118,4 +212,76 @@ impl AOCSolution for Solution {
         });
         Ok((bad_index_sum,distress_packet_indices.into_iter().product()))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::{packet_grammar, PacketList, PacketListItem, PacketPairs, Solution, MAX_PACKET_DEPTH};
+    use crate::{matcher::{Matcher, Printer, ShortMatchNestedListErr}, solution::AOCSolution};
+
+    const EXAMPLE:&str = "[1,1,3,1,1]
+[1,1,5,1,1]
+
+[[1],[2,3,4]]
+[[1],4]
+
+[9]
+[[8,7,6]]
+
+[[4,4],4,4]
+[[4,4],4,4,4]
+
+[7,7,7,7]
+[7,7,7]
+
+[]
+[3]
+
+[[[]]]
+[[]]
+
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9]";
+
+    #[test]
+    fn example() {
+        let pairs:PacketPairs = EXAMPLE.parse().unwrap();
+        assert_eq!(Solution::solve(pairs), Ok((13,140)));
+    }
+
+    #[test]
+    fn too_deep() {
+        let deep = format!("{}\n[]", "[".repeat(100_000));
+        assert_eq!(deep.parse::<PacketPairs>().unwrap_err(), ShortMatchNestedListErr::TooDeep);
+        let max_depth = format!("{}{}", "[".repeat(MAX_PACKET_DEPTH), "]".repeat(MAX_PACKET_DEPTH));
+        assert!(format!("{max_depth}\n{max_depth}").parse::<PacketPairs>().is_ok());
+    }
+
+    #[test]
+    fn deep_cmp() {
+        let nest = |innermost| (0..100_000).fold(PacketList(vec![innermost]), |inner, _| PacketList(vec![PacketListItem::List(inner)]));
+        let (a, b) = (nest(PacketListItem::Value(1)), nest(PacketListItem::Value(2)));
+        assert!(a < b);
+        assert!(nest(PacketListItem::List(PacketList(vec![]))) < a);
+
+        let copy = a.clone();
+        assert!(copy == a && copy != b);
+        assert_eq!(PacketListItem::List(copy), PacketListItem::List(a.clone()));
+        let printed = format!("{a:?}");
+        assert_eq!(printed.len(), 2 * 100_001 + 1);
+        assert!(printed.starts_with("[[") && printed.contains("[1]"));
+        assert_eq!(packet_grammar().print_to_string(&a), printed);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut grammar = packet_grammar();
+        for packet_str in ["[]", "[[]]", "[1,[2,[3,[4,[5,6,7]]]],8,9]", "[[4,4],4,4,4]", "[[[]],10]"] {
+            let packet = grammar.next_match(packet_str).unwrap().matched;
+            let printed = grammar.print_to_string(&packet);
+            assert_eq!(printed, packet_str);
+            assert_eq!(grammar.next_match(&printed).unwrap().matched, packet);
+            assert_eq!(format!("{packet:?}"), packet_str);
+        }
+    }
+}
@@ -1,9 +1,9 @@
 use std::{str::FromStr, ops::Range, marker::PhantomData, fmt::Debug};
 
-use crate::solution::{AOCSolution, Labeled};
+use crate::{solution::{AOCSolution, Labeled}, vec2::Vec2};
 
 type PosCoord = isize;
-type Pos = (PosCoord,PosCoord);
+type Pos = Vec2<PosCoord>;
 
 #[derive(Debug,Clone)]
 pub struct Scanner {
@@ -34,7 +34,7 @@ impl FromStr for ScannersAndBeacons {
             fn split_to_point((almost_x,almost_y):(&str,&str))->Option<Pos> {
                 let x = almost_x.split_once("=").and_then(|(_,x)|x.parse().ok())?;
                 let y = almost_y.split_once("=").and_then(|(_,y)|y.parse().ok())?;
-                Some((x, y))
+                Some(Pos::new(x, y))
             }
             split_to_point(scanner).zip(split_to_point(beacon))
         });
@@ -50,7 +50,7 @@ impl FromStr for ScannersAndBeacons {
 
             
             scanners.push(Scanner {
-                manhattan_to_closest:manhattan(&scanner,&beacon),
+                manhattan_to_closest:scanner.manhattan(beacon).ok_or(())?,
                 position: scanner,
             });
         }
@@ -61,10 +61,6 @@ impl FromStr for ScannersAndBeacons {
     }
 }
 
-#[inline]
-fn manhattan(&(a_x,a_y):&Pos,&(b_x,b_y):&Pos)->usize{
-    a_x.abs_diff(b_x) + a_y.abs_diff(b_y)
-}
 // #[inline]
 // fn min_max<O:Ord+Clone,I:Iterator<Item=O>>(mut iter:I)->Option<(O,O)>{
 //     let first = iter.next()?;
@@ -251,11 +247,11 @@ impl AOCSolution for Solution {
         // const Y:isize = 10;
         let mut covered_ranges:RangeSet<_> = Default::default(); 
         covered_ranges.extend(scanners.iter().filter_map(|s|{
-            let (x,y) = s.position;
+            let Vec2 { x, y } = s.position;
             let remaining = s.manhattan_to_closest.checked_sub(y.abs_diff(Y))? as isize;
             Some(x-remaining..x+remaining+1)
         }));
-        for beacon_x in beacon_positions.iter().filter_map(|&(b_x,b_y)|(b_y==Y).then_some(b_x)) {
+        for beacon_x in beacon_positions.iter().filter_map(|&Vec2 { x: b_x, y: b_y }|(b_y==Y).then_some(b_x)) {
             covered_ranges.remove(beacon_x..beacon_x+1);
         }
         let maybe_count = covered_ranges.ranges().iter().map(|r|r.len()).sum();
@@ -265,7 +261,7 @@ impl AOCSolution for Solution {
             covered_ranges.clear();
             covered_ranges.extend(scanners.iter().filter_map(|s|{
                 // dbg!(target_y,s.position);
-                let (x,y) = s.position;
+                let Vec2 { x, y } = s.position;
                 let remaining = s.manhattan_to_closest.checked_sub(y.abs_diff(target_y))? as isize;
                 Some(((x-remaining).max(0))..((x+remaining+1).max(0)))
             }));
@@ -1,6 +1,6 @@
-use std::{str::FromStr, collections::HashSet, ops::ControlFlow};
+use std::{str::FromStr, collections::HashSet};
 
-use crate::{solution::{Unsolved, AOCSolution, Labeled}, vec2::Vec2, array::next_chunk};
+use crate::{solution::{AOCSolution, Labeled}, vec2::Vec2, cycle::find_cycle};
 
 #[derive(Debug,Clone,Copy,Eq,PartialEq)]
 pub enum JetMove {
@@ -38,166 +38,78 @@ impl FromStr for JetMoves {
     }
 }
 
-pub struct Counted<'a,I>(I,&'a mut usize);
-impl <'a,I:Iterator> Iterator for Counted<'a, I>{
-    type Item = I::Item;
-    fn next(&mut self) -> Option<Self::Item> {
-        *self.1+=1;
-        self.0.next()
-    }
-}
-
 const CAVE_WIDTH:usize = 7;
 
+/// The cave so far, with where the next rock and jet come from
 #[derive(Debug,Clone)]
-pub struct SaveState {
-    block_count:usize,
-    repeat_height:usize,
+struct Chamber<'m> {
+    moves:&'m [JetMove],
+    stationary_rocks:HashSet<Pos>,
+    height:usize,
+    rock_count:usize,
+    jet_index:usize,
 }
-
-fn part_1(moves:&Vec<JetMove>)->usize{
-    let mut stationary_rocks = HashSet::new();
-    let mut max_y_part_1 = 0;
-    let mut count = 0;
-    let mut jet_moves = moves.iter().cycle();
-
-    for rock in ROCK_SHAPES.into_iter().cycle().take(2022){
-        let rock = rock.0.into_iter().chain(rock.1);
-        let mut pos = Pos::new(2,max_y_part_1+3);
-        loop {
-            let Some(jet_move) = jet_moves.next() else {
-                break
-            };
-            match jet_move {
-                JetMove::Left => {
-                    if rock.clone().map(|rock_offset|pos+rock_offset).all(|p|p.x>0 && !stationary_rocks.contains(&Pos::new(p.x-1,p.y))){
-                        pos.x-=1;
-                    }
-                },
-                JetMove::Right => {
-                    if rock.clone().map(|rock_offset|pos+rock_offset).all(|p|p.x!=(CAVE_WIDTH-1) && !stationary_rocks.contains(&Pos::new(p.x+1,p.y))){
-                        pos.x+=1;
-                    }
-                },
-            }
-            if rock.clone().map(|rock_offset|pos+rock_offset).all(|p|p.y>0 && !stationary_rocks.contains(&Pos::new(p.x,p.y-1))){
-                pos.y-=1;
-            }else {
-                break;
-            }
-        };
-        stationary_rocks.extend(rock.clone().map(|rock_offset|pos+rock_offset));
-        for rock_pos_y in rock.map(|rock_offset|pos.y+rock_offset.y+1) {
-            max_y_part_1 = max_y_part_1.max(rock_pos_y);
-        }
+impl <'m> Chamber<'m> {
+    fn new(moves:&'m [JetMove])->Self{
+        Self { moves, stationary_rocks:HashSet::new(), height:0, rock_count:0, jet_index:0 }
     }
-    max_y_part_1
-}
-
-fn part_2(moves:&Vec<JetMove>)->Result<usize,()>{
-    let mut stationary_rocks = HashSet::new();
-    let mut max_y=0;
-    let mut save_state = Option::<(usize,usize)>::None;
-    let mut jet_moves = moves.iter().cycle();
-    let mut rocks = ROCK_SHAPES.into_iter().cycle();
-
-    let (
-        (start_repeat_rock_count,repeat_start_height),
-        (end_repeat_rock_count ,repeat_end_height),
-    ) = loop{
-        let Some(rock) = rocks.next() else {
-            return Err(())
-        };
-        let rock = rock.0.into_iter().chain(rock.1);
-        let mut pos = Pos::new(2,max_y+3);
+    fn is_clear(&self,rock:impl Iterator<Item=Pos>)->bool{
+        rock.into_iter().all(|p|p.x<CAVE_WIDTH && !self.stationary_rocks.contains(&p))
+    }
+    fn drop_rock(&mut self){
+        let (shape,center) = ROCK_SHAPES[self.rock_count%ROCK_SHAPES.len()];
+        let rock = shape.into_iter().chain(center);
+        let mut pos = Pos::new(2,self.height+3);
         loop {
-            let Some(jet_move) = jet_moves.next() else {
-                return Err(());
+            let jet_move = self.moves[self.jet_index];
+            self.jet_index = (self.jet_index+1)%self.moves.len();
+            let pushed = match jet_move {
+                JetMove::Left => pos.x.checked_sub(1).map(|x|Pos::new(x,pos.y)),
+                JetMove::Right => Some(Pos::new(pos.x+1,pos.y)),
             };
-            match jet_move {
-                JetMove::Left => {
-                    if rock.clone().map(|rock_offset|pos+rock_offset).all(|p|p.x>0 && !stationary_rocks.contains(&Pos::new(p.x-1,p.y))){
-                        pos.x-=1;
-                    }
-                },
-                JetMove::Right => {
-                    if rock.clone().map(|rock_offset|pos+rock_offset).all(|p|p.x!=(CAVE_WIDTH-1) && !stationary_rocks.contains(&Pos::new(p.x+1,p.y))){
-                        pos.x+=1;
-                    }
-                },
+            if let Some(pushed) = pushed.filter(|&pushed|self.is_clear(rock.clone().map(|rock_offset|pushed+rock_offset))) {
+                pos = pushed;
             }
-            if rock.clone().map(|rock_offset|pos+rock_offset).all(|p|p.y>0 && !stationary_rocks.contains(&Pos::new(p.x,p.y-1))){
-                pos.y-=1;
-            }else {
-                break;
+            match pos.y.checked_sub(1).map(|y|Pos::new(pos.x,y)) {
+                Some(fallen) if self.is_clear(rock.clone().map(|rock_offset|fallen+rock_offset)) => pos = fallen,
+                _ => break,
             }
-        };
-        stationary_rocks.extend(rock.clone().map(|rock_offset|pos+rock_offset));
-        for rock_pos_y in rock.map(|rock_offset|pos.y+rock_offset.y+1) {
-            max_y = max_y.max(rock_pos_y);
-        }
-        let prev_y=max_y-1;
-        if (0..CAVE_WIDTH).all(|x|stationary_rocks.contains(&Pos::new(x,prev_y)))
-        {
-            if let Some(save_state) = save_state {
-                break (
-                    save_state,
-                    (stationary_rocks.len(),prev_y)
-                );
-            }else{
-                save_state = Some((
-                    stationary_rocks.len(),
-                    prev_y
-                ));
-            };
         }
-    };
-
-    let current_max_y = max_y;
-
-    const A_BILLY:usize = 10usize.pow(12);
-    let repeated_rocks = end_repeat_rock_count-start_repeat_rock_count;
-
-    let rocks_remaining = A_BILLY-start_repeat_rock_count;
-    let rocks_after_repeat = rocks_remaining%repeated_rocks;
-
-    let repeated_height = (repeat_end_height-repeat_start_height)*(rocks_remaining/repeated_rocks);
-    let height_after_repeat = repeated_height+repeat_start_height;
-
-    for rock in rocks.take(rocks_after_repeat) {
-        let rock = rock.0.into_iter().chain(rock.1);
-        let mut pos = Pos::new(2,max_y+3);
-        for jet_move in &mut jet_moves {
-            match jet_move {
-                JetMove::Left => {
-                    if rock.clone().map(|rock_offset|pos+rock_offset).all(|p|p.x>0 && !stationary_rocks.contains(&Pos::new(p.x-1,p.y))){
-                        pos.x-=1;
-                    }
-                },
-                JetMove::Right => {
-                    if rock.clone().map(|rock_offset|pos+rock_offset).all(|p|p.x!=(CAVE_WIDTH-1) && !stationary_rocks.contains(&Pos::new(p.x+1,p.y))){
-                        pos.x+=1;
-                    }
-                },
-            }
-            if rock.clone().map(|rock_offset|pos+rock_offset).all(|p|p.y>0 && !stationary_rocks.contains(&Pos::new(p.x,p.y-1))){
-                pos.y-=1;
-            }else {
-                break;
-            }
-        };
-        stationary_rocks.extend(rock.clone().map(|rock_offset|pos+rock_offset));
+        self.stationary_rocks.extend(rock.clone().map(|rock_offset|pos+rock_offset));
         for rock_pos_y in rock.map(|rock_offset|pos.y+rock_offset.y+1) {
-            max_y = max_y.max(rock_pos_y);
+            self.height = self.height.max(rock_pos_y);
         }
+        self.rock_count+=1;
+    }
+    /// How far below the top each column's highest rock is
+    fn skyline(&self)->[usize;CAVE_WIDTH]{
+        std::array::from_fn(|x|{
+            (0..self.height).rev()
+                .position(|y|self.stationary_rocks.contains(&Pos::new(x,y)))
+                .unwrap_or(self.height)
+        })
     }
-    dbg!(stationary_rocks.iter().max_by_key(|p|p.y),max_y); 
+}
 
-    // 349959777046
-    // 349959777039
+fn part_1(moves:&[JetMove])->usize{
+    let mut chamber = Chamber::new(moves);
+    for _ in 0..2022 {
+        chamber.drop_rock();
+    }
+    chamber.height
+}
 
-    let final_height =(max_y-current_max_y)+height_after_repeat;
-    Ok(final_height)
+fn part_2(moves:&[JetMove])->Result<usize,()>{
+    const A_BILLY:usize = 10usize.pow(12);
+    // Once the next rock, the next jet and the shape of the top all line up again, the cave grows the same way forever
+    let (cycle,heights) = find_cycle(
+        &mut Chamber::new(moves),
+        ROCK_SHAPES.len()*moves.len()*CAVE_WIDTH,
+        Chamber::drop_rock,
+        |chamber|(chamber.rock_count%ROCK_SHAPES.len(),chamber.jet_index,chamber.skyline()),
+        |chamber|chamber.height,
+    ).ok_or(())?;
+    cycle.extrapolate(&heights,A_BILLY).ok_or(())
 }
 
 pub struct Solution;
@@ -207,6 +119,20 @@ impl AOCSolution for Solution {
     type Part2=Labeled<usize>;
     type Err = ();
     fn solve(JetMoves(moves):Self::Input)->Result<(Self::Part1,Self::Part2),()> {
+        if moves.is_empty() {
+            return Err(());
+        }
         Ok(((part_1(&moves),"blocks").into(),(part_2(&moves)?,"blocks").into()))
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use super::{part_1, part_2, JetMoves};
+
+    #[test]
+    fn example() {
+        let JetMoves(moves) = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>".parse().unwrap();
+        assert_eq!(part_1(&moves), 3068);
+        assert_eq!(part_2(&moves), Ok(1514285714288));
+    }
+}
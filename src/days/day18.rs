@@ -10,7 +10,7 @@ pub struct InputStruct(Vec<Vec3<Pos>>);
 impl FromStr for InputStruct {
     type Err=();
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut positions = Delimeted::<Vec<[Pos;3]>,_,_>::new(DelimetedArray::<3,FromStrMatcher<Pos>,char>::new(Default::default(), ','), "\n");
+        let mut positions = Delimeted::<Vec<[Pos;3]>,_,_>::new(DelimetedArray::<3,FromStrMatcher<Pos>,char>::new(Default::default(), ','), "\n").exhaustive();
         let Ok(positions) = positions.next_match(s) else {
             return Err(());
         };
@@ -1,57 +1,76 @@
-use core::slice;
-use std::{str::FromStr, collections::{VecDeque, HashSet}, ops::Add, time::Instant, borrow::Borrow};
+use std::{str::FromStr, borrow::Borrow};
 
 use rayon::prelude::*;
 
-use crate::solution::{Unsolved, AOCSolution, Labeled};
+use crate::{solution::AOCSolution, search::BranchAndBound, enums::{enum_index, EnumIndex, EnumMap}};
 
 type Cost = u16;
 
-#[repr(u16)]
-#[derive(Debug,Clone, Copy,PartialEq, Eq,PartialOrd, Ord)]
-pub enum Resource {
-    Ore,
-    Clay,
-    Obsidian,
-    Geode,
+enum_index!{
+    #[derive(Debug,Clone, Copy,PartialEq, Eq,PartialOrd, Ord)]
+    pub enum Resource {
+        Ore,
+        Clay,
+        Obsidian,
+        Geode,
+    }
 }
 
 #[derive(Debug,Clone,Copy,Hash,PartialEq, Eq)]
 pub struct State {
-    robots:[Cost;4],
-    resources:[Cost;4],
+    robots:EnumMap<Resource,Cost>,
+    resources:EnumMap<Resource,Cost>,
     minutes_remaining: u16
 }
 impl State {
-    pub fn try_build_robot<const RESOURCE:u16>(
+    /// Waits until the robot is affordable, then builds it.
+    /// There's no point building one that won't get to collect anything
+    pub fn build_next_robot(
         mut self,
+        resource:Resource,
         blueprint:&BlueprintCosts
     )-> Option<Self>
     {
-        let robot_count = &mut self.robots[RESOURCE as usize];
-        let max_robot_count = blueprint.max_robots[RESOURCE as usize];
-        if *robot_count < max_robot_count {
-            let robot_cost = blueprint.robot_costs[RESOURCE as usize];
-            let new_resources = [
-                self.resources[Resource::Ore as usize].checked_sub(robot_cost[Resource::Ore as usize])?,
-                self.resources[Resource::Clay as usize].checked_sub(robot_cost[Resource::Clay as usize])?,
-                self.resources[Resource::Obsidian as usize].checked_sub(robot_cost[Resource::Obsidian as usize])?,
-                self.resources[Resource::Geode as usize]
-            ];
-            self.resources = new_resources;
-            *robot_count+=1;
-            Some(self)
-        }else{
-            None
+        if self.robots[resource] >= blueprint.max_robots[resource] {
+            return None;
+        }
+        let robot_cost = blueprint.robot_costs[resource];
+        let mut minutes_waiting = 0;
+        for ((&cost,&stock),&robots) in robot_cost.values().zip(self.resources.values()).zip(self.robots.values()) {
+            if let Some(shortfall) = cost.checked_sub(stock).filter(|&s|s>0) {
+                if robots == 0 {
+                    return None;
+                }
+                minutes_waiting = minutes_waiting.max(shortfall.div_ceil(robots));
+            }
+        }
+        let minutes_taken = minutes_waiting+1;
+        self.minutes_remaining = self.minutes_remaining.checked_sub(minutes_taken).filter(|&m|m>0)?;
+        for (stock,robots) in self.resources.values_mut().zip(self.robots.values()) {
+            *stock += robots*minutes_taken;
+        }
+        for (stock,cost) in self.resources.values_mut().zip(robot_cost.values()) {
+            *stock -= cost;
         }
+        self.robots[resource]+=1;
+        Some(self)
+    }
+    /// The geodes there will be at the end if no more robots are built
+    pub fn final_geodes(&self)->Cost {
+        self.resources[Resource::Geode]+self.robots[Resource::Geode]*self.minutes_remaining
+    }
+    /// As if a geode robot could be built every remaining minute
+    pub fn max_geodes(&self)->Cost {
+        let minutes = self.minutes_remaining;
+        self.final_geodes()+minutes*minutes.saturating_sub(1)/2
     }
 }
 
 
 #[derive(Debug,Clone,Copy)]
 pub struct BlueprintCosts{
-    robot_costs:[[Cost;3];4],
-    max_robots:[Cost;4],
+    robot_costs:EnumMap<Resource,EnumMap<Resource,Cost>>,
+    max_robots:EnumMap<Resource,Cost>,
 }
 impl FromStr for BlueprintCosts {
     type Err= Option<<Cost as FromStr>::Err>;
@@ -79,18 +98,18 @@ impl FromStr for BlueprintCosts {
         );
 
         Ok(Self {
-            robot_costs: [
-                [ore_robot_ore,0,0],
-                [clay_robot_ore,0,0],
-                [obisidian_robot_ore_and_clay.0,obisidian_robot_ore_and_clay.1,0],
-                [geode_robot_ore_and_obsidian.0,0,geode_robot_ore_and_obsidian.1],
-            ],
-            max_robots: [
+            robot_costs: EnumMap::new([
+                EnumMap::new([ore_robot_ore,0,0,0]),
+                EnumMap::new([clay_robot_ore,0,0,0]),
+                EnumMap::new([obisidian_robot_ore_and_clay.0,obisidian_robot_ore_and_clay.1,0,0]),
+                EnumMap::new([geode_robot_ore_and_obsidian.0,0,geode_robot_ore_and_obsidian.1,0]),
+            ]),
+            max_robots: EnumMap::new([
                 ore_robot_ore.max(clay_robot_ore).max(obisidian_robot_ore_and_clay.0).max(geode_robot_ore_and_obsidian.0),
                 obisidian_robot_ore_and_clay.1,
                 geode_robot_ore_and_obsidian.1,
                 Cost::MAX
-            ]
+            ])
         })
     }
 }
@@ -106,59 +125,18 @@ impl FromStr for Blueprints {
 fn sim_robots<B:Borrow<BlueprintCosts>>(steps:u16,iter: impl IndexedParallelIterator<Item=B>)->impl IndexedParallelIterator<Item = Cost>{
     iter.map(move |costs|{
         let costs = costs.borrow();
-        let mut queue = VecDeque::<State>::new();
-        queue.push_back(State{
-            robots: [1,0,0,0],
+        let search = BranchAndBound::new(
+            |&state:&State|Resource::VARIANTS
+                .iter()
+                .filter_map(move |&resource|state.build_next_robot(resource,costs)),
+            State::final_geodes,
+            State::max_geodes,
+        );
+        search.maximize([State{
+            robots: EnumMap::new([1,0,0,0]),
             resources: Default::default(),
             minutes_remaining: steps,
-        });
-        let mut max = State{
-            robots: [1,0,0,0],
-            resources: Default::default(),
-            minutes_remaining: 0,
-        };
-        let mut visited=HashSet::<State>::default();
-        let mut min_minutes = steps;
-        while let Some(mut state) = queue.pop_front() {
-            // println!("-----\n{state:?}");
-            if let Some(minutes_remaining) = state.minutes_remaining.checked_sub(1) {
-                if minutes_remaining < min_minutes {
-                    min_minutes = minutes_remaining;
-                    dbg!(min_minutes);
-                }
-                let deltas = state.robots;
-                let states = [
-                    state.try_build_robot::<0>(&costs),
-                    state.try_build_robot::<1>(&costs),
-                    state.try_build_robot::<2>(&costs),
-                    state.try_build_robot::<3>(&costs),
-                ].into_iter().flatten();
-
-                for mut new_state in states {
-                    for (resource,delta) in new_state.resources.iter_mut().zip(deltas) {
-                        *resource+=delta;
-                    }
-                    new_state.minutes_remaining=minutes_remaining;
-                    if !visited.contains(&new_state) {
-                        visited.insert(new_state);
-                        queue.push_back(new_state);
-                    }
-                }
-                for (resource,delta) in state.resources.iter_mut().zip(deltas) {
-                    *resource+=delta;
-                }
-                state.minutes_remaining=minutes_remaining;
-                if !visited.contains(&state) {
-                    visited.insert(state);
-                    queue.push_back(state);
-                }
-            }else{
-                if max.resources[Resource::Geode as usize] <= state.resources[Resource::Geode as usize] {
-                    max = state
-                }
-            }
-        };
-        max.resources[Resource::Geode as usize]
+        }]).map_or(0,|(geodes,_)|geodes)
     })
 }
 
@@ -169,10 +147,8 @@ impl AOCSolution for Solution {
     type Part2=usize;
     type Err = ();
     fn solve(input:Self::Input)->Result<(Self::Part1,Self::Part2),()> {
-        let sum = sim_robots(24, input.0.par_iter()).enumerate().map(|(i,geodes)|(i+1)*geodes as usize).sum();   
-        dbg!(sum);
-        let trio = sim_robots(32, input.0.par_iter().take(3)).map(|a|a as usize).product();   
-        dbg!(trio);
+        let sum = sim_robots(24, input.0.par_iter()).enumerate().map(|(i,geodes)|(i+1)*geodes as usize).sum();
+        let trio = sim_robots(32, input.0.par_iter().take(3)).map(|a|a as usize).product();
 
         Ok((
             sum,
@@ -180,3 +156,16 @@ impl AOCSolution for Solution {
         ))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Blueprints, Solution};
+    use crate::solution::AOCSolution;
+
+    #[test]
+    fn example() {
+        let input = "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
+Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.";
+        assert_eq!(Solution::solve(input.parse::<Blueprints>().unwrap()), Ok((33,56*62)));
+    }
+}
@@ -1,18 +1,31 @@
 use core::num;
-use std::{str::FromStr, collections::{HashMap, HashSet}, ops::Range, iter::{Flatten, Cloned}};
+use std::{str::FromStr, collections::{HashMap, HashSet}, ops::Range, iter::{Flatten, Cloned}, num::ParseIntError};
 
 use rayon::{option::Iter, range};
 
-use crate::solution::{Unsolved, AOCSolution};
+use crate::{solution::{Unsolved, AOCSolution}, matcher::FromStrMatcher, stream::{MatchStream, StreamError}, or::Or};
 
 type Num = i64;
+/// Where the input stopped being a newline separated list of numbers
+pub type NumbersErr = StreamError<ParseIntError,Option<char>>;
 
 #[derive(Debug,Clone)]
 pub struct Numbers(Vec<Num>);
+impl Numbers {
+    pub fn from_reader<R:std::io::BufRead>(reader:R)->Result<Self,Or<std::io::Error,NumbersErr>> {
+        MatchStream::new(reader, FromStrMatcher::<Num>::MATCHER, '\n').map(|n|n.map_err(|e|match e {
+            StreamError::Io(e)=>Or::Left(e),
+            e=>Or::Right(e)
+        })).collect::<Result<Vec<_>,_>>().map(Numbers)
+    }
+}
 impl FromStr for Numbers {
-    type Err=();
+    type Err=NumbersErr;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.lines().map(Num::from_str).collect::<Result<Vec<_>,_>>().map_err(|_|{}).map(Numbers)
+        Self::from_reader(s.as_bytes()).map_err(|e|match e {
+            Or::Left(e)=>StreamError::Io(e),
+            Or::Right(e)=>e,
+        })
     }
 }
 #[derive(Debug,Clone,PartialEq, Eq)]
@@ -174,7 +187,8 @@ impl <'a,T> Iterator for ShuffledListIter<'a,T> {
 }
 #[cfg(test)]
 mod test {
-    use super::ShuffledList;
+    use super::{Numbers, ShuffledList};
+    use crate::stream::StreamError;
 
     #[test]
     fn te() {
@@ -186,6 +200,12 @@ mod test {
         dbg!(&shuff);
         dbg!(shuff.iter().cloned().collect::<Vec<_>>());
     }
+
+    #[test]
+    fn bad_number() {
+        let err = "1\n2\nthree\n4".parse::<Numbers>().unwrap_err();
+        assert!(matches!(err, StreamError::ExpectedItem(e) if e.index == 4));
+    }
 }
 
 fn mix(numbers:Vec<Num>,mixes:Num, key:Num)->Num {
@@ -227,6 +247,9 @@ impl AOCSolution for Solution {
     type Part1=Num;
     type Part2=Num;
     type Err = ();
+    fn read_input<R: std::io::BufRead>(reader: R) -> Result<Self::Input, Or<std::io::Error, NumbersErr>> {
+        Numbers::from_reader(reader)
+    }
     fn solve(Numbers(numbers):Self::Input)->Result<(Self::Part1,Self::Part2),()> {
         Ok((
             mix(numbers.clone(),1,1),
@@ -1,6 +1,6 @@
-use std::{str::FromStr, collections::HashMap};
+use std::{str::FromStr, collections::HashMap, fmt::Display};
 
-use crate::solution::{Unsolved, AOCSolution};
+use crate::{solution::{Unsolved, AOCSolution}, matcher::{AndMatcher, OrMatcher, FromStrMatcher, Complete, Matcher, Printer}, or::Or};
 
 type Num = u64;
 #[derive(Debug,Clone,Copy,PartialEq, Eq)]
@@ -10,6 +10,63 @@ pub enum Operation {
     Mul,
     Div,
 }
+impl FromStr for Operation {
+    type Err=();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "*" => Ok(Operation::Mul),
+            "+" => Ok(Operation::Add),
+            "-" => Ok(Operation::Sub),
+            "/" => Ok(Operation::Div),
+            _ => Err(())
+        }
+    }
+}
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Operation::Add => "+",
+            Operation::Sub => "-",
+            Operation::Mul => "*",
+            Operation::Div => "/",
+        })
+    }
+}
+#[derive(Debug,Clone,PartialEq,Eq,Hash)]
+pub struct Name(String);
+impl FromStr for Name {
+    type Err=();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.is_empty() && s.chars().all(|c|c.is_ascii_lowercase()) {
+            Ok(Self(s.to_owned()))
+        }else{
+            Err(())
+        }
+    }
+}
+impl Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// `name: number` or `name: name op name`
+type JobGrammar = Complete<AndMatcher<
+    AndMatcher<FromStrMatcher<Name>,&'static str>,
+    OrMatcher<
+        FromStrMatcher<Num>,
+        AndMatcher<AndMatcher<AndMatcher<AndMatcher<FromStrMatcher<Name>,char>,FromStrMatcher<Operation>>,char>,FromStrMatcher<Name>>
+    >
+>>;
+type JobValue = ((Name,()),Or<Num,((((Name,()),Operation),()),Name)>);
+fn job_grammar()->JobGrammar {
+    FromStrMatcher::<Name>::MATCHER.and(": ").and(
+        FromStrMatcher::<Num>::MATCHER.or(
+            FromStrMatcher::<Name>::MATCHER.and(' ').and(FromStrMatcher::<Operation>::MATCHER).and(' ').and(FromStrMatcher::<Name>::MATCHER)
+        )
+    ).complete()
+}
+
 #[derive(Debug,Clone)]
 pub enum Mathkey {
     Operate(Operation,usize,usize),
@@ -21,6 +78,7 @@ pub struct InputStruct {
     root_mathkey:usize,
     humn_mathkey:usize,
     mathkeys: Vec<Mathkey>,
+    names: Vec<Name>,
 }
 
 #[derive(Debug,PartialEq, Eq)]
@@ -120,52 +178,61 @@ impl FromStr for InputStruct {
     type Err=();
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut nodes = Vec::new();
-        let n = &mut nodes;
+        let mut names = Vec::new();
         let mut node_index_by_name = HashMap::new();
+        let mut grammar = job_grammar();
 
         macro_rules! id_for_name {
-            ($name:expr) => {
-                *node_index_by_name.entry($name).or_insert_with(||{
+            ($name:expr) => {{
+                let name:Name = $name;
+                *node_index_by_name.entry(name.clone()).or_insert_with(||{
                     let next_id = nodes.len();
                     nodes.push(None);
+                    names.push(name);
                     next_id
                 })
-            };
+            }};
         }
 
         for line in s.lines() {
-            let name = &line[..4];
+            let ((name,_),job) = grammar.next_match(line).map_err(|_|{})?.matched;
             let index = id_for_name!(name);
-
-            let mut rest = line[6..].split_whitespace();
-            let first = rest.next().ok_or(())?;
-            if let Some(operator) = rest.next() {
-                let second = rest.next().ok_or(())?;
-                let left_index = id_for_name!(first);
-                let right_index = id_for_name!(second);
-                let op = match operator {
-                    "*" => Operation::Mul,
-                    "+" => Operation::Add,
-                    "-" => Operation::Sub,
-                    "/" => Operation::Div,
-                    _ => return Err(())
-                };
-                nodes[index] = Some(Mathkey::Operate(op, left_index, right_index));
-            } else if let Ok(num) = first.parse::<Num>() {
-                nodes[index] = Some(Mathkey::Yell(num));
-            } else {
-                panic!("Hm?")
-            };
+            nodes[index] = Some(match job {
+                Or::Left(num) => Mathkey::Yell(num),
+                Or::Right(((((left,_),op),_),right)) => {
+                    let left_index = id_for_name!(left);
+                    let right_index = id_for_name!(right);
+                    Mathkey::Operate(op, left_index, right_index)
+                }
+            });
         };
-        node_index_by_name.get("root").zip(node_index_by_name.get("humn")).zip(nodes.into_iter().collect::<Option<Vec<_>>>()).ok_or(()).map(move |((root_mathkey,humn_mathkey),mathkeys)|{
+        let root_mathkey = node_index_by_name.get(&Name("root".into())).copied();
+        let humn_mathkey = node_index_by_name.get(&Name("humn".into())).copied();
+        root_mathkey.zip(humn_mathkey).zip(nodes.into_iter().collect::<Option<Vec<_>>>()).ok_or(()).map(move |((root_mathkey,humn_mathkey),mathkeys)|{
             Self{ 
-                root_mathkey:*root_mathkey,
-                humn_mathkey:*humn_mathkey,
-                mathkeys
+                root_mathkey,
+                humn_mathkey,
+                mathkeys,
+                names
             }
         })
     }
 }
+impl Display for InputStruct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let grammar = job_grammar();
+        for (index,mathkey) in self.mathkeys.iter().enumerate() {
+            let name = |i:usize|self.names[i].clone();
+            let job:JobValue = ((name(index),()),match *mathkey {
+                Mathkey::Yell(num) => Or::Left(num),
+                Mathkey::Operate(op,l,r) => Or::Right(((((name(l),()),op),()),name(r))),
+            });
+            grammar.print(&job, f)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
 
 pub struct Solution;
 impl AOCSolution for Solution {
@@ -176,4 +243,40 @@ impl AOCSolution for Solution {
     fn solve(input:Self::Input)->Result<(Self::Part1,Self::Part2),()> {
         Ok((input.eval(input.root_mathkey),input.solve_for(input.root_mathkey,input.humn_mathkey).unwrap()))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::InputStruct;
+
+    const EXAMPLE:&str = "root: pppw + sjmn
+dbpl: 5
+cczh: sllz + lgvd
+zczc: 2
+ptdq: humn - dvpt
+dvpt: 3
+lfqf: 4
+humn: 5
+ljgn: 2
+sjmn: drzm * dbpl
+sllz: 4
+pppw: cczh / lfqf
+lgvd: ljgn * ptdq
+drzm: hmdt - zczc
+hmdt: 32";
+
+    #[test]
+    fn round_trip() {
+        let input:InputStruct = EXAMPLE.parse().unwrap();
+        let printed = input.to_string();
+        let mut printed_lines = printed.lines().collect::<Vec<_>>();
+        let mut example_lines = EXAMPLE.lines().collect::<Vec<_>>();
+        printed_lines.sort();
+        example_lines.sort();
+        assert_eq!(printed_lines, example_lines);
+
+        let reparsed:InputStruct = printed.parse().unwrap();
+        assert_eq!(reparsed.eval(reparsed.root_mathkey), 152);
+        assert_eq!(reparsed.solve_for(reparsed.root_mathkey, reparsed.humn_mathkey), Some(301));
+    }
+}
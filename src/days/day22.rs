@@ -105,7 +105,7 @@ impl FromStr for Map {
             }
         }).collect::<Option<Vec<_>>>().ok_or(())?;
         
-        let steps = FromStrMatcher::<usize>::default().and('L'.or('R').and(FromStrMatcher::<usize>::default()).many::<Vec<_>>()).next_match(steps);
+        let steps = FromStrMatcher::<usize>::default().and('L'.or('R').and(FromStrMatcher::<usize>::default()).many::<Vec<_>>()).complete().next_match(steps.trim_end());
         let steps= steps.unwrap();
 
         let (first_num, (rest,_)) = steps.matched;
@@ -1,9 +1,9 @@
 use std::{str::FromStr, collections::{HashSet, HashMap}};
 
-use crate::{solution::{Unsolved, AOCSolution}, or::Or, days::day9::Dir};
+use crate::{solution::{Unsolved, AOCSolution}, or::Or, days::day9::Dir, vec2::Vec2, grid::{Grid, GridParseErr}};
 
 type Num = i32;
-type Pos = (Num,Num);
+type Pos = Vec2<Num>;
 
 #[repr(u8)]
 #[derive(Debug,Clone,Copy,PartialEq, Eq)]
@@ -14,35 +14,36 @@ pub enum Direction {
     East=3,
 }
 impl Direction{
-    const fn get(&self) -> fn(Pos)->[Pos;3]{
-        [
-            |(x,y)|[(x,y-1),(x-1,y-1),(x+1,y-1)],
-            |(x,y)|[(x,y+1),(x-1,y+1),(x+1,y+1)],
-            |(x,y)|[(x-1,y),(x-1,y-1),(x-1,y+1)],
-            |(x,y)|[(x+1,y),(x+1,y-1),(x+1,y+1)],
-        ][*self as usize]
-    }
-    const fn adjacents(&self,(x,y):Pos) -> [Pos;3]{
+    const fn forward(&self) -> Pos {
         match self {
-            Self::North=>[(x,y-1),(x-1,y-1),(x+1,y-1)],
-            Self::South=>[(x,y+1),(x-1,y+1),(x+1,y+1)],
-            Self::West=>[(x-1,y),(x-1,y-1),(x-1,y+1)],
-            Self::East=>[(x+1,y),(x+1,y-1),(x+1,y+1)],
+            Self::North=>Pos::new(0,-1),
+            Self::South=>Pos::new(0,1),
+            Self::West=>Pos::new(-1,0),
+            Self::East=>Pos::new(1,0),
         }
     }
+    /// The position one step this way, then the two beside that
+    fn adjacents(&self,position:Pos) -> [Pos;3]{
+        let ahead = position+self.forward();
+        let side = self.forward().rotate_cw();
+        [ahead,ahead+side,ahead-side]
+    }
 }
 
 #[derive(Debug,Clone)]
 pub struct InputStruct {
-    elf_positions:HashSet<(Num,Num)>
+    elf_positions:HashSet<Pos>
 }
 impl FromStr for InputStruct {
-    type Err=();
+    type Err=GridParseErr<char>;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let elf_positions = s.lines().enumerate().flat_map(|(row,line)|{
-            line.char_indices().filter_map(move |(col,c)|{
-                (c == '#').then_some((col as _,row as _))
-            })
+        let grid = Grid::parse_chars(s, |c|match c {
+            '#'=>Ok(true),
+            '.'=>Ok(false),
+            _=>Err(c)
+        })?;
+        let elf_positions = grid.indices().filter_map(|((col,row),i)|{
+            grid.data()[i].then_some(Pos::new(col as _,row as _))
         }).collect();
         Ok(Self{
             elf_positions
@@ -50,32 +51,18 @@ impl FromStr for InputStruct {
     }
 }
 
-fn min_max<T:Ord+Clone,I:IntoIterator<Item=(T,T)>>(positions:I)->Option<((T,T),(T,T))>{
+fn min_max<I:IntoIterator<Item=Pos>>(positions:I)->Option<(Pos,Pos)>{
     let mut iter = positions.into_iter();
     let first = iter.next()?;
-    let min_max = iter.fold((first.clone(),first), |(mut min,mut max),(x,y)|{
-        if x<min.0 {
-            min.0=x;
-        } else if max.0 < x {
-            max.0 = x;
-        };
-
-        if y<min.1 {
-            min.1=y;
-        } else if max.1 < y {
-            max.1 = y;
-        };
-        (min,max)
-    });
-    Some(min_max)
+    Some(iter.fold((first,first), |(min,max),position|(min.min(position),max.max(position))))
 }
 fn display(positions:&HashSet<Pos>){
     let Some((min,max)) = min_max(positions.iter().cloned()) else {
         return;
     };
-    let map = (min.1..=max.1).flat_map(|y|{
-        (min.0..=max.0).map(move |x|{
-            if positions.contains(&(x,y)) {
+    let map = (min.y..=max.y).flat_map(|y|{
+        (min.x..=max.x).map(move |x|{
+            if positions.contains(&Pos::new(x,y)) {
                 '#'
             }else{
                 '.'
@@ -90,7 +77,7 @@ fn round(positions:&HashSet<Pos>,order:[Direction;4])->(HashMap<Pos,(Pos,Vec<Pos
     let mut proposed_by:HashMap<Pos,(Pos,Vec<Pos>)> = Default::default();
     let mut new_positions:HashSet<Pos> = Default::default();
     for position in positions {
-        if order.iter().all(|d|d.adjacents(*position).iter().all(|a|!positions.contains(a))){
+        if position.adjacent_neighbors().all(|a|!positions.contains(&a)){
             new_positions.insert(*position);
             continue;
         }
@@ -134,12 +121,6 @@ impl AOCSolution for Solution {
                 [West,  East,  North, South,],
                 [East,  North, South, West, ],
             ]
-            // [
-            //     [North.get(), South.get(), West.get(),  East.get(), ],
-            //     [South.get(), West.get(),  East.get(),  North.get(),],
-            //     [West.get(),  East.get(),  North.get(), South.get(),],
-            //     [East.get(),  North.get(), South.get(), West.get(), ],
-            // ]
         };
         let mut orders = DIRECTION_ORDERS.into_iter().cycle();
         let mut positions = input.elf_positions.clone();
@@ -161,8 +142,8 @@ impl AOCSolution for Solution {
         let Some((min,max)) = min_max(positions.iter().cloned()) else {
             return Err(());
         };
-        let empty = (min.0..=max.0)
-            .flat_map(|x|(min.1..=max.1).map(move |y|(x,y)))
+        let empty = (min.x..=max.x)
+            .flat_map(|x|(min.y..=max.y).map(move |y|Pos::new(x,y)))
             .filter(|pos|!positions.contains(pos)).count();
         let done_round = orders.position(|order|{
             let (proposals,new_positions)= round(&positions,order);
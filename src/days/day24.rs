@@ -1,40 +1,20 @@
-use std::{str::FromStr, ops::{Index, IndexMut}, collections::HashSet, fmt::Debug};
+use std::{str::FromStr, ops::{Index, IndexMut}};
 
 use rayon::vec;
 
-use crate::{solution::{Unsolved, AOCSolution, Labeled}, bitset::{BitSet, DefaultedBytes}};
+use crate::{solution::{Unsolved, AOCSolution, Labeled}, enums::{enum_index, EnumSet}, graph::bfs_to, grid::{Grid, GridParseErr}};
 
 use super::{day18::Pos, day9::Dir};
 
-type BlizzardSquare = BitSet<Direction,DefaultedBytes::<1>>;
+type BlizzardSquare = EnumSet<Direction>;
 
-#[repr(u8)]
-#[derive(Debug,Clone,Copy,PartialEq, Eq)]
-pub enum Direction {
-    Right=0,
-    Down=1,
-    Left=2,
-    Up=3,
-}
-impl From<Direction> for usize {
-    fn from(value: Direction) -> Self {
-        value as _
-    }
-}
-impl TryFrom<usize> for Direction {
-    type Error=usize;
-
-    fn try_from(value: usize) -> Result<Self, Self::Error> {
-        if value < 4 {
-            Ok([
-                Direction::Right,
-                Direction::Down,
-                Direction::Left,
-                Direction::Up
-            ][value])
-        }else{
-            Err(value)
-        }
+enum_index!{
+    #[derive(Debug,Clone,Copy,PartialEq, Eq)]
+    pub enum Direction {
+        Right,
+        Down,
+        Left,
+        Up,
     }
 }
 
@@ -44,80 +24,14 @@ pub enum Position {
     Grid((usize,usize)),
     Exit
 }
-#[derive(Debug,Clone)]
-pub enum PositionIter {
-    Entrance,
-    Grid {
-        pos:(usize,usize),
-        size:(usize,usize),
-        remaining_directions:std::array::IntoIter<Direction,4>
-    },
-    Exit{size:(usize,usize),},
-    Exhausted
-}
-impl Iterator for PositionIter {
-    type Item = Position;
-    fn next(&mut self) -> Option<Self::Item> {
-        match self {
-            PositionIter::Entrance => {
-                *self = PositionIter::Exhausted;
-                Some(Position::Grid((0,0)))
-            },
-            PositionIter::Grid {size, pos, remaining_directions } => {
-                let pos = remaining_directions.find_map(|d|{
-                    match d {
-                        Direction::Up => {
-                            if let Some(j) = pos.1.checked_sub(1){
-                                Some(Position::Grid((pos.0, j)))
-                            }
-                            else {
-                                (pos.0 == 0).then_some(Position::Entrance)
-                            }
-                        },
-                        Direction::Down => {
-                            let new_j = pos.1+1;
-                            if new_j<size.1{
-                                Some(Position::Grid((pos.0, new_j)))
-                            }
-                            else {
-                                size.0.checked_sub(1).and_then(|i|(pos.0 ==i).then_some(Position::Exit))
-                            }
-                        },
-                        Direction::Left => {
-                            pos.0.checked_sub(1).map(|i|Position::Grid((i, pos.1)))
-                        },
-                        Direction::Right => {
-                            let new_i = pos.0+1;
-                            (new_i < size.0).then_some(Position::Grid((new_i, pos.1)))
-                        },
-                    }
-                });
-                if pos.is_none() {
-                    *self = Self::Exhausted;
-                }
-                pos
-            },
-            &mut PositionIter::Exit{size} => {
-                *self = Self::Exhausted;
-                size.0.checked_sub(1).zip(size.1.checked_sub(1)).map(Position::Grid)
-            },
-            PositionIter::Exhausted => None,
-        }
-    }
-}
 #[derive(Debug,Clone,Eq,PartialEq)]
-pub struct BlizzardGrid<T> {
-    size:(usize,usize),
-    // entrance:T,
-    // exit:T,
-    data:Vec<T>
-}
+pub struct BlizzardGrid<T>(Grid<T>);
 impl BlizzardGrid<BlizzardSquare> {
     pub fn str(&self)->String{
-        let wall = "#".repeat(self.size.0);
-        let mid = self.data.chunks_exact(self.size.0).flat_map(|row|{
+        let wall = "#".repeat(self.size().0);
+        let mid = self.0.rows().into_iter().flat_map(|row|{
             std::iter::once('#').chain(row.iter().flat_map(|space|{
-                let mut iter = space.into_iter();
+                let mut iter = space.iter();
                 let Some(first) = iter.next() else {
                     return Some('.');
                 };
@@ -125,9 +39,6 @@ impl BlizzardGrid<BlizzardSquare> {
                     let num = iter.count()+2;
                     return num.to_string().chars().next();
                 };
-                let Ok(first) = first else {
-                    return None;
-                };
                 match first {
                     Direction::Right => Some('>'),
                     Direction::Down => Some('v'),
@@ -140,147 +51,92 @@ impl BlizzardGrid<BlizzardSquare> {
     }
 }
 impl <T> BlizzardGrid<T>{
-    pub fn adjacents_to(&self,position:Position)->PositionIter{
-        match position {
-            Position::Entrance => PositionIter::Entrance,
-            Position::Grid(pos) => {   
-                if pos.0<self.size.0&&pos.1<self.size.1{
-                    PositionIter::Grid { pos, size: self.size, remaining_directions: [
-                        Direction::Down,
-                        Direction::Right,
-                        Direction::Up,
-                        Direction::Left,
-                    ].into_iter() }
-                }else{
-                    PositionIter::Exhausted
-                }
-            },
-            Position::Exit => PositionIter::Exit { size: self.size },
-        }
+    pub fn size(&self)->(usize,usize){
+        (self.0.column_count(),self.0.row_count())
     }
-    pub fn new<I:IntoIterator<Item=J>,J:IntoIterator<Item=T>>(data:I)->Self
-        where I:Debug,J:Debug,T:Debug
-    {
-        let mut data_iters = data.into_iter();
-        let first = data_iters.next();
-        let Some(first) = first else {
-            return Self {
-                size:(0,0),
-                data:Default::default(),
-                // entrance,
-                // exit
-            }
-        };
-        let mut data_vec:Vec<_> = first.into_iter().collect();
-        let mut size = (data_vec.len(),1);
-        for iter in data_iters {
-            data_vec.extend(iter);
-            size.1+=1;
+    pub fn adjacents_to(&self,position:Position)->impl Iterator<Item=Position>+'_{
+        let (width,height) = self.size();
+        let last = width.checked_sub(1).zip(height.checked_sub(1));
+        let (in_grid,outside) = match position {
+            Position::Entrance => (None,[self.0.in_bounds((0,0)).then_some(Position::Grid((0,0))),None]),
+            Position::Exit => (None,[last.map(Position::Grid),None]),
+            Position::Grid(pos) => (Some(pos),[
+                (pos == (0,0)).then_some(Position::Entrance),
+                (Some(pos) == last).then_some(Position::Exit),
+            ]),
         };
-        Self { size, /*entrance, exit,*/ data: data_vec }
+        in_grid.into_iter()
+            .flat_map(|pos|self.0.neighbors4(pos).map(|(neighbor,_)|Position::Grid(neighbor.into())))
+            .chain(outside.into_iter().flatten())
     }
 }
 impl <T> Index<(usize,usize)> for BlizzardGrid<T>{
     type Output=T;
 
     #[inline]
-    fn index(&self, (i,j): (usize,usize)) -> &Self::Output {
-        &self.data[i+j*self.size.0]
+    fn index(&self, pos: (usize,usize)) -> &Self::Output {
+        &self.0[pos]
     }
 }
 impl <T> IndexMut<(usize,usize)> for BlizzardGrid<T>{  
     #[inline]
-    fn index_mut(&mut self, (i,j): (usize,usize)) -> &mut Self::Output {
-        &mut self.data[i+j*self.size.0]
+    fn index_mut(&mut self, pos: (usize,usize)) -> &mut Self::Output {
+        &mut self.0[pos]
     }
 }
 
 impl FromStr for BlizzardGrid<BlizzardSquare> {
-    type Err=();
+    type Err=GridParseErr<char>;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines();
-        lines.next(); // Skip the entrance
-        lines.next_back(); // Skip the exit
-
-        Ok(BlizzardGrid::new( 
-            lines.map(|line|{
-                let mut chars = line.chars();
-                chars.next(); // Skip the wall
-                chars.next_back(); // Skip the wall
-                chars.map(|c|{
-                    let mut set =BitSet::default();
-                    match c {
-                        '<'=> {set.insert(Direction::Left);},
-                        '>'=> {set.insert(Direction::Right);},
-                        '^'=> {set.insert(Direction::Up);},
-                        'v'=> {set.insert(Direction::Down);},
-                        _=>{}
-                    }
-                    set
-                })
-            })
-        ))
+        // Walls are None
+        let map = Grid::parse_chars(s, |c|{
+            let mut set = BlizzardSquare::default();
+            match c {
+                '#'=> return Ok(None),
+                '.'=> {},
+                '<'=> {set.insert(Direction::Left);},
+                '>'=> {set.insert(Direction::Right);},
+                '^'=> {set.insert(Direction::Up);},
+                'v'=> {set.insert(Direction::Down);},
+                _=> return Err(c)
+            }
+            Ok(Some(set))
+        })?;
+        // Everything inside the walls, which leaves out the entrance and exit rows
+        let interior = map.view(
+            1..map.column_count().saturating_sub(1),
+            1..map.row_count().saturating_sub(1),
+        ).ok_or(GridParseErr::Empty)?;
+        Ok(BlizzardGrid(interior.map_ref(|s|s.unwrap_or_default())))
     }
 }
 
-#[inline]
-fn inc_wrap(value:usize, max:usize)->usize {
-    let new_value = value+1;
-    if new_value < max {
-        new_value
-    }else {
-        new_value - max
+impl BlizzardGrid<BlizzardSquare> {
+    /// Whether a square is clear of every blizzard after `time` minutes
+    fn is_clear(&self,(i,j):(usize,usize),time:usize)->bool {
+        let (width,height) = self.size();
+        !self[((i+width-time%width)%width,j)].contains(Direction::Right)
+            && !self[((i+time)%width,j)].contains(Direction::Left)
+            && !self[(i,(j+height-time%height)%height)].contains(Direction::Down)
+            && !self[(i,(j+time)%height)].contains(Direction::Up)
     }
 }
-#[inline]
-fn dec_wrap(value:usize, max:usize)->usize {
-    value.checked_sub(1).unwrap_or(max-1)
-}
 
-fn sim(start:Position,end:&Position,blizzards:&mut BlizzardGrid<BlizzardSquare>)->Option<usize> {
-    let mut current_positions:HashSet<Position> = [start].into_iter().collect();
-    (0..).find(|i|{
-        // dbg!(&current_positions);
-        // println!("{}",blizzards.str());
-        if current_positions.contains(end) {
-            true
-        }else{
-            current_positions=current_positions
-                .iter()
-                .filter(|&&p|{
-                    if let Position::Grid(pos) = p {
-                        blizzards[pos].is_empty()
-                    }else {
-                        true
-                    }
-                })
-                .flat_map(|&p|blizzards.adjacents_to(p).chain(Some(p)))
-                .collect();
-            let mut new_blizzards = vec![
-                BlizzardSquare::default();blizzards.data.len()
-            ];
-            for j in 0..blizzards.size.1 {
-                for i in 0..blizzards.size.0 {
-                    for dir in blizzards[(i,j)]{
-                        let Ok(dir) = dir else {
-                            continue;
-                        };
-                        let next_pos = match dir {
-                            Direction::Right => (inc_wrap(i,blizzards.size.0),j),
-                            Direction::Down => (i,inc_wrap(j,blizzards.size.1)),
-                            Direction::Left => (dec_wrap(i,blizzards.size.0),j),
-                            Direction::Up => (i,dec_wrap(j,blizzards.size.1)),
-                        };
-                        // println!("{:?} moved {dir:?} to {next_pos:?}",(i,j));
-                        new_blizzards[next_pos.0+next_pos.1*blizzards.size.0].insert(dir);
-                    }
-                };
+/// The minutes taken to get from `start` to `end`, setting off `start_time` minutes in
+fn crossing(start:Position,end:Position,start_time:usize,blizzards:&BlizzardGrid<BlizzardSquare>)->Option<usize> {
+    // Every blizzard is back where it started after this many minutes
+    let (width,height) = blizzards.size();
+    let period = width*height;
+    bfs_to([(start,start_time%period)], |(position,time)|{
+        let next_time = (time+1)%period;
+        blizzards.adjacents_to(position).chain(Some(position)).filter(move |&p|{
+            if let Position::Grid(pos) = p {
+                blizzards.is_clear(pos,next_time)
+            }else {
+                true
             }
-            blizzards.data = new_blizzards;
-            false
-        }
-        
-    })
+        }).map(move |p|(p,next_time))
+    }, |(position,_)|position==&end).map(|result|result.cost)
 }
 
 
@@ -290,12 +146,49 @@ impl AOCSolution for Solution {
     type Part1=Labeled<usize>;
     type Part2=Labeled<usize>;
     type Err = ();
-    fn solve(mut blizzards:Self::Input)->Result<(Self::Part1,Self::Part2),()> {
-        let to_exit = sim(Position::Entrance,&Position::Exit,&mut blizzards);
-        let to_start = sim(Position::Exit,&Position::Entrance,&mut blizzards);
-        let to_exit_again = sim(Position::Entrance,&Position::Exit,&mut blizzards);
-        to_exit.zip(to_start.zip(to_exit_again).map(|(a,b)|a+b)).map(|(a,b)|(
-            (a,"minutes").into(),(a+b,"minutes").into()
-        )).ok_or(())
+    fn solve(blizzards:Self::Input)->Result<(Self::Part1,Self::Part2),()> {
+        let to_exit = crossing(Position::Entrance,Position::Exit,0,&blizzards).ok_or(())?;
+        let to_start = crossing(Position::Exit,Position::Entrance,to_exit,&blizzards).ok_or(())?;
+        let to_exit_again = crossing(Position::Entrance,Position::Exit,to_exit+to_start,&blizzards).ok_or(())?;
+        Ok((
+            (to_exit,"minutes").into(),(to_exit+to_start+to_exit_again,"minutes").into()
+        ))
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use super::{crossing, BlizzardGrid, BlizzardSquare, Position};
+    use crate::astar::a_star;
+
+    #[test]
+    fn a_star_matches_crossing() {
+        let blizzards:BlizzardGrid<BlizzardSquare> = "#.######
+#>>.<^<#
+#.<..<<#
+#>v.><>#
+#<^v^^>#
+#####.##".parse().unwrap();
+        let (width,height) = blizzards.size();
+        let period = width*height;
+        // Steps left to the exit, ignoring the blizzards
+        let to_exit = |&(position,_):&(Position,usize)|match position {
+            Position::Entrance => width+height,
+            Position::Grid((x,y)) => (width-1-x)+(height-1-y)+1,
+            Position::Exit => 0,
+        };
+
+        let result = a_star([(Position::Entrance,0)], |(position,time)|{
+            let next_time = (time+1)%period;
+            let blizzards = &blizzards;
+            blizzards.adjacents_to(position).chain(Some(position)).filter(move |&p|{
+                if let Position::Grid(pos) = p {
+                    blizzards.is_clear(pos,next_time)
+                }else {
+                    true
+                }
+            }).map(move |p|((p,next_time),1usize))
+        }, to_exit, |&(position,_)|position == Position::Exit).unwrap();
+        assert_eq!(result.cost, 18);
+        assert_eq!(Some(result.cost), crossing(Position::Entrance,Position::Exit,0,&blizzards));
+    }
+}
@@ -1,9 +1,12 @@
 use std::{str::FromStr, fmt::{Display, Debug}};
 
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,PartialEq,Eq)]
 pub struct SNAFU(Vec<i8>);
 impl Display for SNAFU {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("0");
+        }
         f.write_str(&self.0.iter().rev().filter_map(|c|match c{
             2=>Some('2'),
             1=>Some('1'),
@@ -37,10 +40,36 @@ impl From<isize> for SNAFU {
         Self(digits)
     }
 }
+impl FromStr for SNAFU {
+    type Err=Option<char>;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(None);
+        }
+        let mut digits = s.chars().rev().map(|c|match c{
+            '2'=>Ok(2),
+            '1'=>Ok(1),
+            '0'=>Ok(0),
+            '-'=>Ok(-1),
+            '='=>Ok(-2),
+            c=>Err(Some(c))
+        }).collect::<Result<Vec<i8>,_>>()?;
+        // Leading zeros don't change the value, and zero itself has no digits
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Ok(Self(digits))
+    }
+}
 
 
 
-use crate::solution::{Unsolved, AOCSolution};
+use crate::{solution::{Unsolved, AOCSolution}, matcher::{Delimeted, FromStrMatcher, Matcher}};
+
+type InputGrammar = Delimeted<Vec<SNAFU>,FromStrMatcher<SNAFU>,char>;
+fn input_grammar()->InputGrammar {
+    Delimeted::new(FromStrMatcher::MATCHER, '\n').exhaustive()
+}
 
 #[derive(Debug,Clone)]
 pub struct InputStruct {
@@ -49,16 +78,8 @@ pub struct InputStruct {
 impl FromStr for InputStruct {
     type Err=();
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self{numbers:s.lines().map(|line|{
-            SNAFU(line.chars().rev().filter_map(|c|match c{
-                '2'=>Some(2),
-                '1'=>Some(1),
-                '0'=>Some(0),
-                '-'=>Some(-1),
-                '='=>Some(-2),
-                _=>None
-            }).collect::<Vec<i8>>())
-        }).collect()})
+        let numbers = input_grammar().next_match(s.trim_end()).map_err(|_|{})?.matched;
+        Ok(Self{numbers})
     }
 }
 
@@ -73,4 +94,19 @@ impl AOCSolution for Solution {
         let sum = sum.into();
         Ok((sum,Unsolved))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::{input_grammar, SNAFU};
+    use crate::matcher::{Matcher, Printer};
+
+    #[test]
+    fn round_trip() {
+        let numbers = (0..=2022).chain([314159265]).map(SNAFU::from).collect::<Vec<_>>();
+        let mut grammar = input_grammar();
+        let printed = grammar.print_to_string(&numbers);
+        assert_eq!(grammar.next_match(&printed).unwrap().matched, numbers);
+        assert!(printed.ends_with("\n1121-1110-1=0"));
+    }
+}
@@ -3,7 +3,7 @@ use std::str::FromStr;
 
 
 use crate::solution::{AOCSolution, Labeled};
-use crate::bitset::{BitSet, DefaultedBytes};
+use crate::bitset::BitSet;
 
 pub struct RSChar(u8);
 impl RSChar {
@@ -54,7 +54,7 @@ impl TryFrom<char> for RSChar {
 }
 
 
-type Chars = BitSet<RSChar,DefaultedBytes<10>>;
+type Chars = BitSet<RSChar,[u64;1]>;
 
 #[derive(Debug,Clone,PartialEq, Eq)]
 pub struct Rucksack(Chars);
@@ -87,7 +87,7 @@ impl AOCSolution for Solution {
     type Err = ();
     fn solve(rucksacks:Self::Input)->Result<(Self::Part1,Self::Part2),()> {
         let priority_sum = rucksacks.0.iter().map(|(l,r)|{
-            (l.0.clone()&r.0.clone()).iter().map(|b|{
+            (l.0 & r.0).iter().map(|b|{
                 b.unwrap().priority() as usize
             }).sum::<usize>()
         }).sum();
@@ -4,7 +4,7 @@ use std::fmt::Debug;
 use std::str::FromStr;
 
 
-use crate::solution::{AOCSolution};
+use crate::{solution::AOCSolution, iter::first_distinct_window};
 
 
 #[derive(Debug,Clone)]
@@ -24,20 +24,8 @@ impl AOCSolution for Solution {
     type Part2=usize;
     type Err = ();
     fn solve(signal:Self::Input)->Result<(Self::Part1,Self::Part2),()> {
-        let a= signal.0.windows(4).position(|chars|{
-            let mut iter = chars.iter();
-            chars.iter().all(|c|{
-                iter.next();
-                !iter.clone().any(|i|i==c)
-            })
-        });
-        let b = signal.0.windows(14).position(|chars|{
-            let mut iter = chars.iter();
-            chars.iter().all(|c|{
-                iter.next();
-                !iter.clone().any(|i|i==c)
-            })
-        });
-        Ok((a.unwrap()+4,b.unwrap()+14))
+        let start_of_packet = first_distinct_window(&signal.0,4).ok_or(())?+4;
+        let start_of_message = first_distinct_window(&signal.0,14).ok_or(())?+14;
+        Ok((start_of_packet,start_of_message))
     }
 }
\ No newline at end of file
@@ -1,38 +1,25 @@
-use std::{str::FromStr, borrow::{Borrow, BorrowMut}, fmt::Debug};
+use std::{str::FromStr, borrow::{Borrow, BorrowMut}, fmt::{Debug, Display}};
 
-use crate::{solution::{AOCSolution, Labeled}, grid::Grid};
+use crate::{solution::{AOCSolution, Labeled}, grid::{Grid, GridParseErr}};
 
 #[derive(Debug,Clone)]
 pub struct TreeGrid {
     heights:Grid<u8>,
 }
 impl FromStr for TreeGrid {
-    type Err=();
+    type Err=GridParseErr<char>;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines().peekable();
-        let column_count = lines.peek().cloned().map(str::len).unwrap_or(0);
-        let mut heights = Grid::new(column_count);
-        for line in lines {
-            let line_heights = line.chars().map(|c|match c {
-                '0'=>0,
-                '1'=>1,
-                '2'=>2,
-                '3'=>3,
-                '4'=>4,
-                '5'=>5,
-                '6'=>6,
-                '7'=>7,
-                '8'=>8,
-                '9'=>9,
-                _=>panic!("ah!")
-            });
-            drop(heights.insert_row(line_heights));
-        };
+        let heights = Grid::parse_chars(s, |c|c.to_digit(10).map(|d|d as u8).ok_or(c))?;
         Ok(Self{
             heights,
         })
     }
 }
+impl Display for TreeGrid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.heights.display(|&h|h).fmt(f)
+    }
+}
 
 fn tree_visibility_easy<H:IntoIterator, V:IntoIterator>(tree_heights: H, tree_visiblities: V)
 where 
@@ -56,25 +56,11 @@ pub fn tail_positions<const N:usize,I:IntoIterator<Item=(Dir,i8)>>(steps:I) -> H
             let mut knots_mut = knots.iter_mut();
             let mut prev_knot = knots_mut.next().unwrap();
             for other_knot in knots_mut {
-                let diff = *prev_knot - *other_knot;
-
-                let [x,y] = [diff.x,diff.y].map(isize::abs);
-
-                if x==1 && y ==1 {
-                    // Do nothing
+                if prev_knot.chebyshev(*other_knot) <= 1 {
+                    // Still touching, so this knot and the rest stay put
                     break;
-                } else if x == 0 { 
-                    if y >= 2 {
-                        other_knot.y += diff.y.signum();
-                    }
-                }else if y == 0 { 
-                    if x >= 2 {
-                        other_knot.x += diff.x.signum();
-                    }
-                } else {
-                    other_knot.x += diff.x.signum();
-                    other_knot.y += diff.y.signum();
                 }
+                *other_knot += (*prev_knot - *other_knot).signum();
                 prev_knot = other_knot;
             }
             // dbg!(knots);
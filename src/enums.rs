@@ -0,0 +1,257 @@
+use std::{
+    fmt::Debug,
+    hash::Hash,
+    marker::PhantomData,
+    ops::{BitAnd, BitOr, Index, IndexMut, Not, Sub},
+};
+
+use crate::bitset::BitSet;
+
+/// A fieldless enum whose variants are numbered densely from 0, so it can index sets and arrays.
+/// Implement it with [`enum_index!`]
+pub trait EnumIndex: Copy + 'static {
+    /// Every variant, in index order
+    const VARIANTS: &'static [Self];
+    const COUNT: usize = Self::VARIANTS.len();
+    /// An array with a slot per variant
+    type Array<V>: AsRef<[V]> + AsMut<[V]>;
+    fn index(self) -> usize;
+    #[allow(dead_code)]
+    fn from_index(index: usize) -> Option<Self> {
+        Self::VARIANTS.get(index).copied()
+    }
+    fn array_from_fn<V>(f: impl FnMut(Self) -> V) -> Self::Array<V>;
+}
+
+/// Declares a fieldless enum and implements [`EnumIndex`] for it, numbering the variants in order
+macro_rules! enum_index {
+    ($(#[$meta:meta])* $vis:vis enum $name:ident { $($variant:ident),* $(,)? }) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $($variant),*
+        }
+        impl $crate::enums::EnumIndex for $name {
+            const VARIANTS: &'static [Self] = &[$($name::$variant),*];
+            type Array<V> = [V; [$($name::$variant),*].len()];
+            fn index(self) -> usize {
+                self as usize
+            }
+            fn array_from_fn<V>(mut f: impl FnMut(Self) -> V) -> Self::Array<V> {
+                std::array::from_fn(|i| f(Self::VARIANTS[i]))
+            }
+        }
+    };
+}
+pub(crate) use enum_index;
+
+/// A set of enum variants, packed into a single word, so only for enums with at most 64 variants
+pub struct EnumSet<E> {
+    bits: BitSet<usize, [u64; 1]>,
+    phantom_variants: PhantomData<E>,
+}
+impl<E: EnumIndex> EnumSet<E> {
+    pub fn new() -> Self {
+        const { assert!(E::COUNT <= 64, "too many variants for one word") };
+        Self {
+            bits: BitSet::with_capacity(E::COUNT),
+            phantom_variants: PhantomData,
+        }
+    }
+    #[allow(dead_code)]
+    pub fn all() -> Self {
+        !Self::new()
+    }
+    pub fn contains(&self, variant: E) -> bool {
+        self.bits.contains(variant.index())
+    }
+    /// Adds `variant`, returning whether it was already there
+    pub fn insert(&mut self, variant: E) -> bool {
+        self.bits.insert(variant.index())
+    }
+    /// Takes out `variant`, returning whether it was there
+    #[allow(dead_code)]
+    pub fn remove(&mut self, variant: E) -> bool {
+        self.bits.remove(variant.index())
+    }
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+    /// The variants in index order
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = E> + '_ {
+        self.bits.members().map(|index| E::VARIANTS[index])
+    }
+}
+impl<E: EnumIndex> Default for EnumSet<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<E> Clone for EnumSet<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<E> Copy for EnumSet<E> {}
+impl<E> PartialEq for EnumSet<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits == other.bits
+    }
+}
+impl<E> Eq for EnumSet<E> {}
+impl<E> Hash for EnumSet<E> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.bits.hash(state);
+    }
+}
+impl<E: EnumIndex + Debug> Debug for EnumSet<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+impl<E: EnumIndex> Extend<E> for EnumSet<E> {
+    fn extend<I: IntoIterator<Item = E>>(&mut self, iter: I) {
+        self.bits.extend(iter.into_iter().map(E::index));
+    }
+}
+impl<E: EnumIndex> FromIterator<E> for EnumSet<E> {
+    fn from_iter<I: IntoIterator<Item = E>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+impl<E: EnumIndex> BitAnd for EnumSet<E> {
+    type Output = Self;
+    fn bitand(mut self, rhs: Self) -> Self::Output {
+        self.bits &= rhs.bits;
+        self
+    }
+}
+impl<E: EnumIndex> BitOr for EnumSet<E> {
+    type Output = Self;
+    fn bitor(mut self, rhs: Self) -> Self::Output {
+        self.bits |= rhs.bits;
+        self
+    }
+}
+impl<E: EnumIndex> Sub for EnumSet<E> {
+    type Output = Self;
+    fn sub(mut self, rhs: Self) -> Self::Output {
+        self.bits -= rhs.bits;
+        self
+    }
+}
+/// Every variant not in the set
+impl<E: EnumIndex> Not for EnumSet<E> {
+    type Output = Self;
+    fn not(self) -> Self::Output {
+        Self {
+            bits: !self.bits,
+            phantom_variants: PhantomData,
+        }
+    }
+}
+
+/// A value for every variant of an enum, stored inline in an array
+pub struct EnumMap<E: EnumIndex, V>(E::Array<V>);
+impl<E: EnumIndex, V> EnumMap<E, V> {
+    /// A map from an array holding the value for each variant in index order
+    pub fn new(values: E::Array<V>) -> Self {
+        Self(values)
+    }
+    pub fn from_fn(f: impl FnMut(E) -> V) -> Self {
+        Self(E::array_from_fn(f))
+    }
+    /// The values in variant order
+    pub fn values(&self) -> std::slice::Iter<'_, V> {
+        self.0.as_ref().iter()
+    }
+    pub fn values_mut(&mut self) -> std::slice::IterMut<'_, V> {
+        self.0.as_mut().iter_mut()
+    }
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (E, &V)> {
+        E::VARIANTS.iter().copied().zip(self.values())
+    }
+    pub fn map<U>(&self, mut f: impl FnMut(E, &V) -> U) -> EnumMap<E, U> {
+        EnumMap::from_fn(|variant| f(variant, &self[variant]))
+    }
+}
+impl<E: EnumIndex, V> Index<E> for EnumMap<E, V> {
+    type Output = V;
+    fn index(&self, variant: E) -> &V {
+        &self.0.as_ref()[variant.index()]
+    }
+}
+impl<E: EnumIndex, V> IndexMut<E> for EnumMap<E, V> {
+    fn index_mut(&mut self, variant: E) -> &mut V {
+        &mut self.0.as_mut()[variant.index()]
+    }
+}
+impl<E: EnumIndex, V: Default> Default for EnumMap<E, V> {
+    fn default() -> Self {
+        Self::from_fn(|_| V::default())
+    }
+}
+impl<E: EnumIndex, V: Clone> Clone for EnumMap<E, V> {
+    fn clone(&self) -> Self {
+        self.map(|_, value| value.clone())
+    }
+}
+impl<E: EnumIndex, V: Copy> Copy for EnumMap<E, V> where E::Array<V>: Copy {}
+impl<E: EnumIndex, V: PartialEq> PartialEq for EnumMap<E, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_ref() == other.0.as_ref()
+    }
+}
+impl<E: EnumIndex, V: Eq> Eq for EnumMap<E, V> {}
+impl<E: EnumIndex, V: Hash> Hash for EnumMap<E, V> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.as_ref().hash(state);
+    }
+}
+impl<E: EnumIndex + Debug, V: Debug> Debug for EnumMap<E, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EnumIndex, EnumMap, EnumSet};
+
+    enum_index! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        enum Suit {
+            Clubs,
+            Diamonds,
+            Hearts,
+            Spades,
+        }
+    }
+
+    #[test]
+    fn sets_and_maps() {
+        assert_eq!(Suit::COUNT, 4);
+        assert_eq!(Suit::from_index(2), Some(Suit::Hearts));
+
+        let red: EnumSet<Suit> = [Suit::Hearts, Suit::Diamonds].into_iter().collect();
+        assert!(red.iter().eq([Suit::Diamonds, Suit::Hearts]));
+        assert!((!red).iter().eq([Suit::Clubs, Suit::Spades]));
+        assert_eq!(EnumSet::<Suit>::all().len(), 4);
+        assert!((red - EnumSet::all()).is_empty());
+        let mut hearts = red;
+        assert!(hearts.remove(Suit::Diamonds));
+        assert!(!hearts.remove(Suit::Diamonds));
+        assert!(hearts.iter().eq([Suit::Hearts]));
+
+        let mut counts = EnumMap::<Suit, u32>::default();
+        counts[Suit::Spades] += 3;
+        assert_eq!(counts, EnumMap::new([0, 0, 0, 3]));
+        assert_eq!(counts.map(|suit, &n| red.contains(suit) || n > 0), EnumMap::new([false, true, true, true]));
+    }
+}
@@ -0,0 +1,460 @@
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BinaryHeap, HashMap, HashSet, VecDeque},
+    hash::Hash,
+    iter::Sum,
+    ops::Sub,
+};
+
+use crate::grid::Grid;
+
+/// The zero of a summable weight, without needing a `Zero` trait
+#[inline]
+pub(crate) fn zero<T: Sum>() -> T {
+    std::iter::empty().sum()
+}
+
+/// A path found by one of the searches here or in [`astar`](crate::astar), with how much work the search took to find it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult<S, W> {
+    pub cost: W,
+    pub path: Vec<S>,
+    /// States popped from the queue and had their neighbors generated
+    pub expanded: usize,
+    /// Entries pushed onto the queue, including the initial states
+    pub pushed: usize,
+}
+
+/// Shortest distances from the start states of a search, with the previous state on each shortest path
+#[derive(Debug, Clone)]
+pub struct ShortestPaths<S, W> {
+    distances: HashMap<S, W>,
+    prev_state: HashMap<S, S>,
+}
+impl<S: Hash + Eq + Clone, W> ShortestPaths<S, W> {
+    fn new() -> Self {
+        Self {
+            distances: HashMap::default(),
+            prev_state: HashMap::default(),
+        }
+    }
+    pub fn distance(&self, state: &S) -> Option<&W> {
+        self.distances.get(state)
+    }
+    #[allow(dead_code)]
+    pub fn distances(&self) -> &HashMap<S, W> {
+        &self.distances
+    }
+    #[allow(dead_code)]
+    pub fn into_distances(self) -> HashMap<S, W> {
+        self.distances
+    }
+    /// The path from the closest start state to `state`, including both ends
+    pub fn path_to(&self, state: &S) -> Option<Vec<S>> {
+        if !self.distances.contains_key(state) {
+            return None;
+        }
+        let mut path = vec![state.clone()];
+        let mut path_curr = state;
+        while let Some(prev) = self.prev_state.get(path_curr) {
+            path.push(prev.clone());
+            path_curr = prev;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+struct SearchOutcome<S, W> {
+    paths: ShortestPaths<S, W>,
+    found: Option<S>,
+    expanded: usize,
+    pushed: usize,
+}
+impl<S: Hash + Eq + Clone, W: Clone> SearchOutcome<S, W> {
+    fn into_result(self) -> Option<SearchResult<S, W>> {
+        let found = self.found?;
+        Some(SearchResult {
+            cost: self.paths.distance(&found)?.clone(),
+            path: self.paths.path_to(&found)?,
+            expanded: self.expanded,
+            pushed: self.pushed,
+        })
+    }
+}
+
+fn bfs_search<
+    S: Hash + Eq + Clone,
+    Ss: IntoIterator<Item = S>,
+    N: Fn(S) -> Ns,
+    Ns: IntoIterator<Item = S>,
+    C: FnMut(&S) -> bool,
+>(
+    initial_states: Ss,
+    get_neighbors: N,
+    mut is_complete: C,
+) -> SearchOutcome<S, usize> {
+    let mut paths = ShortestPaths::new();
+    let mut states_to_visit = VecDeque::new();
+    let mut expanded = 0;
+    let mut pushed = 0;
+    for initial_state in initial_states {
+        if let Entry::Vacant(v) = paths.distances.entry(initial_state.clone()) {
+            v.insert(0);
+            states_to_visit.push_back(initial_state);
+            pushed += 1;
+        }
+    }
+    while let Some(current_state) = states_to_visit.pop_front() {
+        if is_complete(&current_state) {
+            return SearchOutcome { paths, found: Some(current_state), expanded, pushed };
+        }
+        expanded += 1;
+        let neighbor_distance = paths.distances[&current_state] + 1;
+        for neighbor in get_neighbors(current_state.clone()) {
+            if let Entry::Vacant(v) = paths.distances.entry(neighbor.clone()) {
+                v.insert(neighbor_distance);
+                paths.prev_state.insert(neighbor.clone(), current_state.clone());
+                states_to_visit.push_back(neighbor);
+                pushed += 1;
+            }
+        }
+    }
+    SearchOutcome { paths, found: None, expanded, pushed }
+}
+
+/// Distances (in steps) to every state reachable from the initial states
+#[allow(dead_code)]
+pub fn bfs<S: Hash + Eq + Clone, Ss: IntoIterator<Item = S>, N: Fn(S) -> Ns, Ns: IntoIterator<Item = S>>(
+    initial_states: Ss,
+    get_neighbors: N,
+) -> ShortestPaths<S, usize> {
+    bfs_search(initial_states, get_neighbors, |_| false).paths
+}
+
+/// The fewest steps from any of the initial states to a complete one
+pub fn bfs_to<
+    S: Hash + Eq + Clone,
+    Ss: IntoIterator<Item = S>,
+    N: Fn(S) -> Ns,
+    Ns: IntoIterator<Item = S>,
+    C: Fn(&S) -> bool,
+>(
+    initial_states: Ss,
+    get_neighbors: N,
+    is_complete: C,
+) -> Option<SearchResult<S, usize>> {
+    bfs_search(initial_states, get_neighbors, is_complete).into_result()
+}
+
+/// The fewest steps from any of the initial states to each of the `targets` that can be reached,
+/// stopping as soon as every target has been found
+pub fn bfs_to_all<
+    S: Hash + Eq + Clone,
+    Ss: IntoIterator<Item = S>,
+    N: Fn(S) -> Ns,
+    Ns: IntoIterator<Item = S>,
+    Ts: IntoIterator<Item = S>,
+>(
+    initial_states: Ss,
+    get_neighbors: N,
+    targets: Ts,
+) -> HashMap<S, usize> {
+    let targets: HashSet<S> = targets.into_iter().collect();
+    let mut unvisited_targets = targets.len();
+    let paths = bfs_search(initial_states, get_neighbors, |state| {
+        if targets.contains(state) {
+            unvisited_targets -= 1;
+        }
+        unvisited_targets == 0
+    })
+    .paths;
+    targets
+        .into_iter()
+        .filter_map(|target| Some((target.clone(), *paths.distance(&target)?)))
+        .collect()
+}
+
+fn dijkstra_search<
+    S: Hash + Eq + Clone,
+    Ss: IntoIterator<Item = S>,
+    W: Sum + Clone + Ord,
+    N: Fn(S) -> Ns,
+    Ns: IntoIterator<Item = (S, W)>,
+    C: Fn(&S) -> bool,
+>(
+    initial_states: Ss,
+    get_neighbors: N,
+    is_complete: C,
+) -> SearchOutcome<S, W> {
+    let mut paths = ShortestPaths::new();
+    // The heap holds indices into `states`, so `S` doesn't have to be `Ord`
+    let mut states = Vec::new();
+    let mut states_to_visit = BinaryHeap::new();
+    let mut expanded = 0;
+    for initial_state in initial_states {
+        if let Entry::Vacant(v) = paths.distances.entry(initial_state.clone()) {
+            v.insert(zero());
+            states_to_visit.push(Reverse((zero::<W>(), states.len())));
+            states.push(initial_state);
+        }
+    }
+    while let Some(Reverse((distance, index))) = states_to_visit.pop() {
+        let current_state = &states[index];
+        if paths.distances[current_state] < distance {
+            // A shorter route to this state was pushed after this entry
+            continue;
+        }
+        if is_complete(current_state) {
+            let pushed = states.len();
+            return SearchOutcome { paths, found: Some(states.swap_remove(index)), expanded, pushed };
+        }
+        expanded += 1;
+        let current_state = current_state.clone();
+        for (neighbor, edge) in get_neighbors(current_state.clone()) {
+            let tentative_distance: W = [distance.clone(), edge].into_iter().sum();
+            let new_shortest = match paths.distances.entry(neighbor.clone()) {
+                Entry::Occupied(mut o) => {
+                    let shorter = &tentative_distance < o.get();
+                    if shorter {
+                        o.insert(tentative_distance.clone());
+                    }
+                    shorter
+                }
+                Entry::Vacant(v) => {
+                    v.insert(tentative_distance.clone());
+                    true
+                }
+            };
+            if new_shortest {
+                paths.prev_state.insert(neighbor.clone(), current_state.clone());
+                states_to_visit.push(Reverse((tentative_distance, states.len())));
+                states.push(neighbor);
+            }
+        }
+    }
+    let pushed = states.len();
+    SearchOutcome { paths, found: None, expanded, pushed }
+}
+
+/// Distances to every state reachable from the initial states, through non-negative edge weights
+#[allow(dead_code)]
+pub fn dijkstra<
+    S: Hash + Eq + Clone,
+    Ss: IntoIterator<Item = S>,
+    W: Sum + Clone + Ord,
+    N: Fn(S) -> Ns,
+    Ns: IntoIterator<Item = (S, W)>,
+>(
+    initial_states: Ss,
+    get_neighbors: N,
+) -> ShortestPaths<S, W> {
+    dijkstra_search(initial_states, get_neighbors, |_| false).paths
+}
+
+/// The cheapest path from any of the initial states to a complete one
+#[allow(dead_code)]
+pub fn dijkstra_to<
+    S: Hash + Eq + Clone,
+    Ss: IntoIterator<Item = S>,
+    W: Sum + Clone + Ord,
+    N: Fn(S) -> Ns,
+    Ns: IntoIterator<Item = (S, W)>,
+    C: Fn(&S) -> bool,
+>(
+    initial_states: Ss,
+    get_neighbors: N,
+    is_complete: C,
+) -> Option<SearchResult<S, W>> {
+    dijkstra_search(initial_states, get_neighbors, is_complete).into_result()
+}
+
+/// Shortest distances between every pair of the nodes `0..node_count`.
+///
+/// The grids are indexed `(to, from)`, so each row holds the distances out of one node.
+#[derive(Debug, Clone)]
+pub struct AllPairsShortestPaths<W> {
+    distances: Grid<Option<W>>,
+    prev_node: Grid<Option<usize>>,
+}
+impl<W> AllPairsShortestPaths<W> {
+    fn new(node_count: usize) -> Self {
+        let mut distances = Grid::new(node_count);
+        let mut prev_node = Grid::new(node_count);
+        for _ in 0..node_count {
+            let _ = distances.insert_row((0..node_count).map(|_| None));
+            let _ = prev_node.insert_row((0..node_count).map(|_| None));
+        }
+        Self { distances, prev_node }
+    }
+    #[allow(dead_code)]
+    pub fn distance(&self, from: usize, to: usize) -> Option<&W> {
+        self.distances.get((to, from))?.as_ref()
+    }
+    /// The nodes on a shortest path, including both ends
+    #[allow(dead_code)]
+    pub fn path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        self.distance(from, to)?;
+        let mut path = vec![to];
+        let mut path_curr = to;
+        while path_curr != from {
+            path_curr = self.prev_node[(path_curr, from)]?;
+            path.push(path_curr);
+        }
+        path.reverse();
+        Some(path)
+    }
+    pub fn into_distances(self) -> Grid<Option<W>> {
+        self.distances
+    }
+}
+
+/// All-pairs shortest paths over a dense graph, in O(V³).
+/// Every neighbor must be below `node_count`.
+pub fn floyd_warshall<W: Sum + Clone + Ord, N: Fn(usize) -> Ns, Ns: IntoIterator<Item = (usize, W)>>(
+    node_count: usize,
+    get_neighbors: N,
+) -> AllPairsShortestPaths<W> {
+    let mut paths = AllPairsShortestPaths::new(node_count);
+    for from in 0..node_count {
+        paths.distances[(from, from)] = Some(zero());
+        for (to, edge) in get_neighbors(from) {
+            let distance = &mut paths.distances[(to, from)];
+            if distance.as_ref().is_none_or(|d| &edge < d) {
+                *distance = Some(edge);
+                paths.prev_node[(to, from)] = Some(from);
+            }
+        }
+    }
+    for k in 0..node_count {
+        for i in 0..node_count {
+            let Some(i_to_k) = paths.distances[(k, i)].clone() else {
+                continue;
+            };
+            for j in 0..node_count {
+                let Some(k_to_j) = paths.distances[(j, k)].clone() else {
+                    continue;
+                };
+                let through_k: W = [i_to_k.clone(), k_to_j].into_iter().sum();
+                if paths.distances[(j, i)].as_ref().is_none_or(|d| &through_k < d) {
+                    paths.distances[(j, i)] = Some(through_k);
+                    paths.prev_node[(j, i)] = paths.prev_node[(j, k)];
+                }
+            }
+        }
+    }
+    paths
+}
+
+/// All-pairs shortest paths over a sparse graph, where edge weights may be negative.
+/// Every neighbor must be below `node_count`, and there is no answer if the graph has a negative cycle.
+#[allow(dead_code)]
+pub fn johnson<
+    W: Sum + Clone + Ord + Sub<Output = W>,
+    N: Fn(usize) -> Ns,
+    Ns: IntoIterator<Item = (usize, W)>,
+>(
+    node_count: usize,
+    get_neighbors: N,
+) -> Option<AllPairsShortestPaths<W>> {
+    // Bellman-Ford from a virtual node with a zero weight edge to every other node
+    let mut potential: Vec<W> = (0..node_count).map(|_| zero()).collect();
+    for round in 0..=node_count {
+        let mut relaxed = false;
+        for from in 0..node_count {
+            for (to, edge) in get_neighbors(from) {
+                let through_from: W = [potential[from].clone(), edge].into_iter().sum();
+                if through_from < potential[to] {
+                    potential[to] = through_from;
+                    relaxed = true;
+                }
+            }
+        }
+        if !relaxed {
+            break;
+        }
+        if round == node_count {
+            return None;
+        }
+    }
+
+    // Reweighting by the potentials makes every edge non-negative without changing which paths are shortest
+    let potential = &potential;
+    let get_neighbors = &get_neighbors;
+    let mut paths = AllPairsShortestPaths::new(node_count);
+    for from in 0..node_count {
+        let reweighted = dijkstra([from], |s| {
+            get_neighbors(s).into_iter().map(move |(to, edge)| {
+                let weight: W = [edge, potential[s].clone()].into_iter().sum();
+                (to, weight - potential[to].clone())
+            })
+        });
+        for (&to, distance) in reweighted.distances() {
+            let distance: W = [distance.clone(), potential[to].clone()].into_iter().sum();
+            paths.distances[(to, from)] = Some(distance - potential[from].clone());
+            paths.prev_node[(to, from)] = reweighted.prev_state.get(&to).copied();
+        }
+    }
+    Some(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::{bfs, bfs_to, bfs_to_all, dijkstra, dijkstra_to, floyd_warshall, johnson};
+
+    const EDGES: [(usize, usize, i32); 6] = [(0, 1, 4), (0, 2, 1), (2, 1, 2), (1, 3, 1), (2, 3, 5), (3, 4, 3)];
+    fn neighbors(s: usize) -> impl Iterator<Item = (usize, i32)> {
+        EDGES.iter().filter(move |e| e.0 == s).map(|e| (e.1, e.2))
+    }
+
+    #[test]
+    fn single_source() {
+        let steps = bfs([0], |s| neighbors(s).map(|n| n.0));
+        assert_eq!(steps.distance(&4), Some(&3));
+        assert_eq!(bfs_to([0], |s| neighbors(s).map(|n| n.0), |&s| s == 3).unwrap().path, [0, 1, 3]);
+
+        let distances = dijkstra([0], neighbors);
+        assert_eq!(distances.distance(&4), Some(&7));
+        assert_eq!(distances.path_to(&4).unwrap(), [0, 2, 1, 3, 4]);
+        assert_eq!(dijkstra_to([0], neighbors, |&s| s == 3).unwrap().cost, 4);
+    }
+
+    #[test]
+    fn multiple_targets() {
+        let to_targets = bfs_to_all([0], |s| neighbors(s).map(|n| n.0), [1, 3, 4, 5]);
+        assert_eq!(to_targets.len(), 3);
+        assert_eq!((to_targets[&1], to_targets[&3], to_targets[&4]), (1, 2, 3));
+
+        // Both targets are found without expanding anything past them
+        let expanded = Cell::new(0);
+        let counted_neighbors = |s| {
+            expanded.set(expanded.get() + 1);
+            neighbors(s).map(|n| n.0)
+        };
+        assert_eq!(bfs_to_all([0], counted_neighbors, [1, 2]).len(), 2);
+        assert_eq!(expanded.get(), 2);
+    }
+
+    #[test]
+    fn all_pairs() {
+        let dense = floyd_warshall(5, neighbors);
+        let sparse = johnson(5, neighbors).unwrap();
+        for from in 0..5 {
+            for to in 0..5 {
+                assert_eq!(dense.distance(from, to), sparse.distance(from, to));
+                assert_eq!(
+                    dense.path(from, to).map(|p| p.len()),
+                    sparse.path(from, to).map(|p| p.len())
+                );
+            }
+        }
+        assert_eq!(sparse.path(0, 4).unwrap(), [0, 2, 1, 3, 4]);
+        assert_eq!(sparse.distance(4, 0), None);
+
+        let negative = johnson(3, |s| [(1, 2), (2, -3)].into_iter().skip(s).take(1)).unwrap();
+        assert_eq!(negative.distance(0, 2), Some(&-1));
+        assert!(johnson(2, |s| [(1 - s, -1)]).is_none());
+    }
+}
@@ -1,9 +1,25 @@
 use std::{
-    ops::{Index, IndexMut},
+    fmt::Display,
+    ops::{Index, IndexMut, Range},
     vec::Drain,
 };
 
-#[derive(Debug, Clone)]
+use crate::vec2::{Vec2, ADJACENT, ORTHOGONAL};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridParseErr<E> {
+    Empty,
+    /// A row with a different number of cells to the first row
+    RaggedRow { row: usize, expected: usize, found: usize },
+    Cell { row: usize, column: usize, error: E },
+}
+
+/// A rectangular grid of cells, indexed by `(column, row)`.
+///
+/// Cells are stored row by row, and everything that walks the whole grid
+/// (`data`, `rows`, `indices`, `map` and friends) visits them in that same row-major order.
+/// Only `cols` and `cols_mut` go column by column.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Grid<T> {
     data: Vec<T>,
     column_count: usize,
@@ -17,6 +33,7 @@ impl<T> Grid<T> {
         }
     }
     #[inline]
+    #[allow(dead_code)]
     pub fn from_parts(data: Vec<T>, column_count: usize) -> Option<Self> {
         if data.len() % column_count == 0 {
             Some(Self { data, column_count })
@@ -24,22 +41,135 @@ impl<T> Grid<T> {
             None
         }
     }
-    pub fn remove_column(&mut self, column:usize) {
-        if column < self.column_count {
-            for index in (column..self.data.len()).step_by(column).rev() {
-                self.data.remove(index);
+    /// Parses a rectangular map with a cell per character, ignoring any trailing line breaks
+    pub fn parse_chars<E, F: FnMut(char) -> Result<T, E>>(
+        s: &str,
+        mut parse_cell: F,
+    ) -> Result<Self, GridParseErr<E>> {
+        let mut data = Vec::new();
+        let mut column_count = None;
+        for (row, line) in s.trim_end_matches(['\n', '\r']).lines().enumerate() {
+            let row_start = data.len();
+            for (column, c) in line.chars().enumerate() {
+                data.push(parse_cell(c).map_err(|error| GridParseErr::Cell { row, column, error })?);
+            }
+            let found = data.len() - row_start;
+            let expected = *column_count.get_or_insert(found);
+            if found != expected {
+                return Err(GridParseErr::RaggedRow { row, expected, found });
+            }
+        }
+        match column_count {
+            Some(column_count) if column_count > 0 => Ok(Self { data, column_count }),
+            _ => Err(GridParseErr::Empty),
+        }
+    }
+    /// Renders the grid a row per line, with each cell formatted by `format_cell`
+    pub fn display<C: Display, F: Fn(&T) -> C>(&self, format_cell: F) -> GridDisplay<'_, T, F> {
+        GridDisplay { grid: self, format_cell }
+    }
+    /// Borrows the rectangle spanning `columns` and `rows`, or `None` if it doesn't fit inside the grid
+    pub fn view(&self, columns: Range<usize>, rows: Range<usize>) -> Option<GridView<'_, T>> {
+        GridView::from(self).view(columns, rows)
+    }
+    /// Removes a column in a single pass, returning its cells from top to bottom
+    pub fn remove_column(&mut self, column: usize) -> Option<Vec<T>> {
+        if column >= self.column_count {
+            return None;
+        }
+        let old_column_count = self.column_count;
+        let row_count = self.row_count();
+        let mut removed = Vec::with_capacity(row_count);
+        let kept = Vec::with_capacity(self.data.len() - row_count);
+        let old_data = std::mem::replace(&mut self.data, kept);
+        for (i, cell) in old_data.into_iter().enumerate() {
+            if i % old_column_count == column {
+                removed.push(cell);
+            } else {
+                self.data.push(cell);
             }
-            self.column_count-=1;
         }
+        self.column_count -= 1;
+        Some(removed)
+    }
+    /// Inserts a column before `column`, which needs a cell for every row, from top to bottom.
+    /// The cells are handed back if there are the wrong number of them
+    #[allow(dead_code)]
+    pub fn insert_column(&mut self, column: usize, cells: impl IntoIterator<Item = T>) -> Result<(), Vec<T>> {
+        let cells: Vec<T> = cells.into_iter().collect();
+        if column > self.column_count || cells.len() != self.row_count() {
+            return Err(cells);
+        }
+        let old_column_count = self.column_count;
+        let expanded = Vec::with_capacity(self.data.len() + cells.len());
+        let old_data = std::mem::replace(&mut self.data, expanded);
+        let mut old_cells = old_data.into_iter();
+        for cell in cells {
+            self.data.extend(old_cells.by_ref().take(column));
+            self.data.push(cell);
+            self.data.extend(old_cells.by_ref().take(old_column_count - column));
+        }
+        self.column_count += 1;
+        Ok(())
     }
     pub fn remove_row(&mut self, row:usize) -> Option<Drain<T>> {
-        if row < self.rows().into_iter().count() {
+        if row < self.row_count() {
             let start = row*self.column_count;
             Some(self.data.drain(start..start+self.column_count))
         }else {
             None
         }
     }
+    /// Moves every cell in a single pass, into a grid with `column_count` columns,
+    /// given where each old `(column, row)` ends up.
+    /// A grid without any rows comes back as it is, since the row count is what sets the column count
+    fn rearranged<F: Fn(usize, usize) -> (usize, usize)>(self, column_count: usize, new_position: F) -> Self {
+        if self.data.is_empty() {
+            return self;
+        }
+        let old_column_count = self.column_count;
+        let mut data: Vec<Option<T>> = (0..self.data.len()).map(|_| None).collect();
+        for (i, cell) in self.data.into_iter().enumerate() {
+            let (c, r) = new_position(i % old_column_count, i / old_column_count);
+            data[r * column_count + c] = Some(cell);
+        }
+        Self {
+            data: data.into_iter().flatten().collect(),
+            column_count,
+        }
+    }
+    /// Swaps rows and columns, so `(column, row)` moves to `(row, column)`
+    #[allow(dead_code)]
+    pub fn transpose(self) -> Self {
+        let row_count = self.row_count();
+        self.rearranged(row_count, |c, r| (r, c))
+    }
+    /// Turns the grid a quarter turn clockwise, so the first row becomes the last column
+    #[allow(dead_code)]
+    pub fn rotate_cw(self) -> Self {
+        let row_count = self.row_count();
+        self.rearranged(row_count, |c, r| (row_count - 1 - r, c))
+    }
+    /// Turns the grid a quarter turn anticlockwise, so the first row becomes the first column
+    #[allow(dead_code)]
+    pub fn rotate_ccw(self) -> Self {
+        let (column_count, row_count) = (self.column_count, self.row_count());
+        self.rearranged(row_count, |c, r| (r, column_count - 1 - c))
+    }
+    /// Mirrors the grid left to right
+    #[allow(dead_code)]
+    pub fn flip_horizontal(&mut self) {
+        for row in self.rows_mut() {
+            row.reverse();
+        }
+    }
+    /// Mirrors the grid top to bottom
+    #[allow(dead_code)]
+    pub fn flip_vertical(&mut self) {
+        // Reversing every cell turns the grid upside down but also mirrors each row, which is then undone
+        self.data.reverse();
+        self.flip_horizontal();
+    }
     pub fn insert_row(&mut self, row: impl Iterator<Item = T>) -> Result<(), Drain<T>> {
         let mut inserted_columns = 0;
         self.data.extend(row.inspect(|_| {
@@ -62,7 +192,7 @@ impl<T> Grid<T> {
         &self,
         row:usize
     ) -> Option<&[T]> {
-        if row < self.rows().into_iter().count() {
+        if row < self.row_count() {
             let start = row*self.column_count;
             self.data.get(start..start+self.column_count)
         }else {
@@ -100,11 +230,12 @@ impl<T> Grid<T> {
         })
     }
 
+    /// Each `(column, row)` with its index into `data`, in row-major order
     pub fn indices(&self) -> impl Iterator<Item = ((usize, usize), usize)> {
-        let row_count = self.data.len() / self.column_count;
+        let row_count = self.row_count();
         let column_count = self.column_count;
-        (0..column_count)
-            .flat_map(move |c| (0..row_count).map(move |r| ((c, r), r * column_count + c)))
+        (0..row_count)
+            .flat_map(move |r| (0..column_count).map(move |c| ((c, r), r * column_count + c)))
     }
     pub fn map_ref<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
         Grid {
@@ -124,20 +255,66 @@ impl<T> Grid<T> {
             column_count: self.column_count,
         }
     }
-    pub fn get(&self, (c, r): (usize, usize)) -> Option<&T> {
+    pub fn column_count(&self) -> usize {
+        self.column_count
+    }
+    pub fn row_count(&self) -> usize {
+        self.data.len().checked_div(self.column_count).unwrap_or(0)
+    }
+    pub fn in_bounds(&self, pos: impl Into<Vec2<usize>>) -> bool {
+        let Vec2 { x: c, y: r } = pos.into();
+        c < self.column_count && r < self.row_count()
+    }
+    pub fn get(&self, pos: impl Into<Vec2<usize>>) -> Option<&T> {
+        let Vec2 { x: c, y: r } = pos.into();
         if c < self.column_count {
             self.data.get(r * self.column_count + c)
         } else {
             None
         }
     }
-    pub fn get_mut(&mut self, (c, r): (usize, usize)) -> Option<&mut T> {
+    #[allow(dead_code)]
+    pub fn get_mut(&mut self, pos: impl Into<Vec2<usize>>) -> Option<&mut T> {
+        let Vec2 { x: c, y: r } = pos.into();
         if c < self.column_count {
             self.data.get_mut(r * self.column_count + c)
         } else {
             None
         }
     }
+    /// The in-bounds cells directly above, below, left and right of `pos`, or none if `pos` is out of bounds
+    pub fn neighbors4(&self, pos: impl Into<Vec2<usize>>) -> impl Iterator<Item = (Vec2<usize>, &T)> {
+        let pos = pos.into();
+        let in_bounds = self.in_bounds(pos);
+        ORTHOGONAL.into_iter().filter(move |_| in_bounds).filter_map(move |offset| {
+            let neighbor = pos.checked_add_signed(offset)?;
+            Some((neighbor, self.get(neighbor)?))
+        })
+    }
+    /// The in-bounds cells surrounding `pos`, including diagonals
+    #[allow(dead_code)]
+    pub fn neighbors8(&self, pos: impl Into<Vec2<usize>>) -> impl Iterator<Item = (Vec2<usize>, &T)> {
+        let pos = pos.into();
+        let in_bounds = self.in_bounds(pos);
+        ADJACENT.into_iter().filter(move |_| in_bounds).filter_map(move |offset| {
+            let neighbor = pos.checked_add_signed(offset)?;
+            Some((neighbor, self.get(neighbor)?))
+        })
+    }
+    /// The four orthogonal neighbors of `pos`, wrapping around to the opposite edge of the grid
+    #[allow(dead_code)]
+    pub fn wrapping_neighbors(&self, pos: impl Into<Vec2<usize>>) -> impl Iterator<Item = (Vec2<usize>, &T)> {
+        let pos = pos.into();
+        let in_bounds = self.in_bounds(pos);
+        let (column_count, row_count) = (self.column_count, self.row_count());
+        ORTHOGONAL.into_iter().filter(move |_| in_bounds).map(move |offset| {
+            let neighbor = Vec2::new(
+                (pos.x + column_count).wrapping_add_signed(offset.x.into()) % column_count,
+                (pos.y + row_count).wrapping_add_signed(offset.y.into()) % row_count,
+            );
+            (neighbor, &self[neighbor])
+        })
+    }
 }
 
 impl<T> Index<(usize, usize)> for Grid<T> {
@@ -151,3 +328,260 @@ impl<T> IndexMut<(usize, usize)> for Grid<T> {
         &mut self.data[r * self.column_count + c]
     }
 }
+impl<T> Index<Vec2<usize>> for Grid<T> {
+    type Output = T;
+    fn index(&self, pos: Vec2<usize>) -> &Self::Output {
+        &self[(pos.x, pos.y)]
+    }
+}
+impl<T> IndexMut<Vec2<usize>> for Grid<T> {
+    fn index_mut(&mut self, pos: Vec2<usize>) -> &mut Self::Output {
+        &mut self[(pos.x, pos.y)]
+    }
+}
+
+pub struct GridDisplay<'g, T, F> {
+    grid: &'g Grid<T>,
+    format_cell: F,
+}
+impl<T, C: Display, F: Fn(&T) -> C> Display for GridDisplay<'_, T, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, row) in self.grid.rows().into_iter().enumerate() {
+            if i != 0 {
+                f.write_str("\n")?;
+            }
+            for cell in row {
+                write!(f, "{}", (self.format_cell)(cell))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A borrowed rectangle of a [`Grid`], indexed by `(column, row)` from its own top left corner.
+///
+/// Each of its rows is a slice of the grid's row, so nothing is copied until [`GridView::map_ref`] or [`GridView::to_grid`]
+#[derive(Debug)]
+pub struct GridView<'g, T> {
+    grid: &'g Grid<T>,
+    origin: Vec2<usize>,
+    column_count: usize,
+    row_count: usize,
+}
+impl<T> Clone for GridView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for GridView<'_, T> {}
+impl<'g, T> From<&'g Grid<T>> for GridView<'g, T> {
+    fn from(grid: &'g Grid<T>) -> Self {
+        Self {
+            grid,
+            origin: Vec2::new(0, 0),
+            column_count: grid.column_count(),
+            row_count: grid.row_count(),
+        }
+    }
+}
+impl<'g, T> GridView<'g, T> {
+    /// A smaller rectangle within this view, with `columns` and `rows` relative to this view
+    pub fn view(&self, columns: Range<usize>, rows: Range<usize>) -> Option<Self> {
+        if columns.start > columns.end || columns.end > self.column_count || rows.start > rows.end || rows.end > self.row_count {
+            return None;
+        }
+        Some(Self {
+            grid: self.grid,
+            origin: self.origin + Vec2::new(columns.start, rows.start),
+            column_count: columns.len(),
+            row_count: rows.len(),
+        })
+    }
+    /// Where the view's top left corner is in the underlying grid
+    #[allow(dead_code)]
+    pub fn origin(&self) -> Vec2<usize> {
+        self.origin
+    }
+    #[allow(dead_code)]
+    pub fn column_count(&self) -> usize {
+        self.column_count
+    }
+    #[allow(dead_code)]
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+    pub fn in_bounds(&self, pos: impl Into<Vec2<usize>>) -> bool {
+        let Vec2 { x: c, y: r } = pos.into();
+        c < self.column_count && r < self.row_count
+    }
+    #[allow(dead_code)]
+    pub fn get(&self, pos: impl Into<Vec2<usize>>) -> Option<&'g T> {
+        let pos = pos.into();
+        if self.in_bounds(pos) {
+            self.grid.get(self.origin + pos)
+        } else {
+            None
+        }
+    }
+    pub fn row(&self, row: usize) -> Option<&'g [T]> {
+        if row < self.row_count {
+            let start = self.origin.x;
+            self.grid.row(self.origin.y + row)?.get(start..start + self.column_count)
+        } else {
+            None
+        }
+    }
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &'g [T]> + ExactSizeIterator {
+        let view = *self;
+        (0..self.row_count).map(move |r| view.row(r).unwrap_or_default())
+    }
+    #[allow(dead_code)]
+    pub fn cols(&self) -> impl DoubleEndedIterator<Item = impl DoubleEndedIterator<Item = &'g T>> {
+        let (grid, origin, row_count) = (self.grid, self.origin, self.row_count);
+        (0..self.column_count).map(move |c| (0..row_count).map(move |r| &grid[origin + Vec2::new(c, r)]))
+    }
+    /// Every cell in row-major order
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &'g T> {
+        self.rows().flatten()
+    }
+    pub fn map_ref<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid {
+            data: self.iter().map(f).collect(),
+            column_count: self.column_count,
+        }
+    }
+    #[allow(dead_code)]
+    pub fn to_grid(self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        self.map_ref(T::clone)
+    }
+}
+impl<T> Index<(usize, usize)> for GridView<'_, T> {
+    type Output = T;
+    fn index(&self, (c, r): (usize, usize)) -> &Self::Output {
+        assert!(self.in_bounds((c, r)), "({c}, {r}) is outside the view");
+        &self.grid[self.origin + Vec2::new(c, r)]
+    }
+}
+impl<T> Index<Vec2<usize>> for GridView<'_, T> {
+    type Output = T;
+    fn index(&self, pos: Vec2<usize>) -> &Self::Output {
+        &self[(pos.x, pos.y)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grid, GridParseErr};
+    use crate::vec2::Vec2;
+
+    #[test]
+    fn neighbors() {
+        // 0 1 2
+        // 3 4 5
+        let grid = Grid::from_parts((0..6).collect(), 3).unwrap();
+        let values = |iter: &mut dyn Iterator<Item = (Vec2<usize>, &i32)>| {
+            let mut values: Vec<_> = iter.map(|(pos, &v)| {
+                assert_eq!(grid[pos], v);
+                v
+            }).collect();
+            values.sort();
+            values
+        };
+        assert_eq!(values(&mut grid.neighbors4((0, 0))), [1, 3]);
+        assert_eq!(values(&mut grid.neighbors4((1, 1))), [1, 3, 5]);
+        assert_eq!(values(&mut grid.neighbors8((1, 0))), [0, 2, 3, 4, 5]);
+        assert_eq!(values(&mut grid.wrapping_neighbors((0, 0))), [1, 2, 3, 3]);
+        assert_eq!(grid.neighbors4((3, 0)).count(), 0);
+        assert!(grid.in_bounds((2, 1)) && !grid.in_bounds((0, 2)));
+    }
+
+    #[test]
+    fn parse_chars() {
+        let digit = |c: char| c.to_digit(10).ok_or(c);
+        let grid = Grid::parse_chars("123\n456\n\n", digit).unwrap();
+        assert_eq!(grid.data(), &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(grid.display(|&d| d).to_string(), "123\n456");
+
+        assert_eq!(
+            Grid::parse_chars("123\n45\n", digit),
+            Err(GridParseErr::RaggedRow { row: 1, expected: 3, found: 2 })
+        );
+        assert_eq!(
+            Grid::parse_chars("123\n4x6", digit),
+            Err(GridParseErr::Cell { row: 1, column: 1, error: 'x' })
+        );
+        assert_eq!(Grid::parse_chars("\n", digit), Err(GridParseErr::Empty));
+    }
+
+    // 0 1 2
+    // 3 4 5
+    fn small() -> Grid<i32> {
+        Grid::from_parts((0..6).collect(), 3).unwrap()
+    }
+
+    #[test]
+    fn columns() {
+        let mut grid = small();
+        assert_eq!(grid.remove_column(0), Some(vec![0, 3]));
+        assert_eq!(grid.data(), &[1, 2, 4, 5]);
+        assert_eq!(grid.remove_column(1), Some(vec![2, 5]));
+        assert_eq!(grid.data(), &[1, 4]);
+        assert_eq!(grid.remove_column(1), None);
+
+        grid.insert_column(1, [7, 8]).unwrap();
+        grid.insert_column(0, [9, 10]).unwrap();
+        assert_eq!(grid.data(), &[9, 1, 7, 10, 4, 8]);
+        assert_eq!(grid.insert_column(0, [0]), Err(vec![0]));
+        assert_eq!(grid.insert_column(4, [0, 0]), Err(vec![0, 0]));
+    }
+
+    #[test]
+    fn reorientation() {
+        assert_eq!(small().transpose().data(), &[0, 3, 1, 4, 2, 5]);
+        assert_eq!(small().rotate_cw().display(|&d| d).to_string(), "30\n41\n52");
+        assert_eq!(small().rotate_ccw().display(|&d| d).to_string(), "25\n14\n03");
+        assert_eq!(small().rotate_cw().rotate_cw().rotate_ccw().rotate_ccw(), small());
+
+        let mut flipped = small();
+        flipped.flip_horizontal();
+        assert_eq!(flipped.data(), &[2, 1, 0, 5, 4, 3]);
+        flipped.flip_vertical();
+        assert_eq!(flipped.data(), &[5, 4, 3, 2, 1, 0]);
+
+        let empty = Grid::<i32>::new(3);
+        for turned in [empty.clone().transpose(), empty.clone().rotate_cw(), empty.clone().rotate_ccw()] {
+            assert_eq!(turned.column_count(), 3);
+            assert_eq!(turned.rows().into_iter().count(), 0);
+        }
+    }
+
+    #[test]
+    fn row_major_indices() {
+        let grid = small();
+        assert!(grid.indices().all(|(pos, i)| grid[pos] == i as i32));
+        assert!(grid.indices().map(|(_, i)| i).eq(0..6));
+    }
+
+    #[test]
+    fn views() {
+        // 0  1  2  3
+        // 4  5  6  7
+        // 8  9 10 11
+        let grid = Grid::from_parts((0..12).collect::<Vec<i32>>(), 4).unwrap();
+        let view = grid.view(1..3, 1..3).unwrap();
+        assert_eq!((view.column_count(), view.row_count()), (2, 2));
+        assert!(view.rows().eq([&[5, 6][..], &[9, 10]]));
+        assert!(view.cols().map(|col| col.copied().collect::<Vec<_>>()).eq([[5, 9], [6, 10]]));
+        assert!(view.iter().copied().eq([5, 6, 9, 10]));
+        assert_eq!((view[(1, 0)], view.get((2, 0))), (6, None));
+
+        let inner = view.view(1..2, 0..2).unwrap();
+        assert_eq!(inner.origin(), Vec2::new(2, 1));
+        assert_eq!(inner.to_grid().data(), &[6, 10]);
+        assert!(grid.view(3..5, 0..1).is_none());
+        assert_eq!(grid.view(2..2, 0..3).unwrap().iter().count(), 0);
+    }
+}
@@ -1,179 +1,430 @@
-// use std::{mem::MaybeUninit};
+use std::{collections::HashMap, hash::Hash, mem::MaybeUninit, ops::ControlFlow};
 
-// pub struct ArrayWindows<const N:usize, I:Iterator>{
-//     array:Option<[I::Item;N]>,
-//     iter:I,
-// }
-// impl <const N:usize, I:Iterator> ArrayWindows<N,I>{
-//     pub fn new(mut iter:I)->Self{
-//         let mut array = [();N].map(|_|MaybeUninit::<I::Item>::uninit());
+use crate::array::next_chunk;
 
-//         let mut initialized_elements = 0usize;
+use rayon::iter::{
+    plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer},
+    ParallelIterator,
+};
 
-//         for (array_item,item) in array.iter_mut().zip(iter.by_ref()) {
-//             array_item.write(item);
-//             initialized_elements+=1;
-//         }
-
-//         Self {
-//             array: {
-//                 if initialized_elements == N {
-//                     Some(array.map(|val|unsafe {val.assume_init()}))
-//                 } else {
-//                     None
-//                 }
-//             },
-//             iter
-//         }
-//     }
-//     pub fn advance(&mut self) -> Option<&mut [I::Item;N]> {
-//         let next = self.iter.next()?;
-//         let array = self.array.as_mut().take()?;
-//         crate::array::push_to_end(array, next);
-//         Some(array)
-//     }
-// }
-// impl <const N:usize, I:Iterator> Iterator for ArrayWindows<N,I>
-//     where I::Item:Clone
-// {
-//     type Item=[I::Item;N];
-
-//     fn next(&mut self) -> Option<Self::Item> {
-//         let next = self.iter.next()?;
-//         let array = self.array.as_mut().take()?;
-//         crate::array::push_to_end(array, next);
-//         Some(array.clone())
-//     }
-// }
-
-// #[cfg(test)]
-// mod test {
-//     use super::ArrayWindows;
-
-//     #[test]
-//     pub fn a(){
-//         for i in ArrayWindows::<3,_>::new(0usize..20).step_by(3) {
-//             dbg!(i);
-//         }
-//     }
-// }
+/// Every run of `N` consecutive items, as arrays, for any iterator
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct ArrayWindows<const N: usize, I: Iterator> {
+    window: Option<[I::Item; N]>,
+    iter: I,
+    /// Whether `window` has been yielded yet
+    started: bool,
+}
+impl<const N: usize, I: Iterator> ArrayWindows<N, I> {
+    /// Panics if `N` is 0
+    #[allow(dead_code)]
+    pub fn new<T: IntoIterator<IntoIter = I>>(iter: T) -> Self {
+        assert!(N > 0, "windows must have at least one item");
+        let mut iter = iter.into_iter();
+        Self {
+            window: next_chunk(&mut iter).ok(),
+            iter,
+            started: false,
+        }
+    }
+}
+impl<const N: usize, I: Iterator> Iterator for ArrayWindows<N, I>
+where
+    I::Item: Clone,
+{
+    type Item = [I::Item; N];
+    fn next(&mut self) -> Option<Self::Item> {
+        let window = self.window.as_mut()?;
+        if self.started {
+            let Some(next) = self.iter.next() else {
+                self.window = None;
+                return None;
+            };
+            window.rotate_left(1);
+            window[N - 1] = next;
+        }
+        self.started = true;
+        Some(window.clone())
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.window.is_none() {
+            return (0, Some(0));
+        }
+        let unstarted = usize::from(!self.started);
+        let (lower, upper) = self.iter.size_hint();
+        (lower.saturating_add(unstarted), upper.and_then(|upper| upper.checked_add(unstarted)))
+    }
+}
 
-use std::{mem::MaybeUninit, ops::ControlFlow};
+/// Where the first window of `k` items that are all different starts, in a single pass
+pub fn first_distinct_window<T: Hash + Eq, I: IntoIterator<Item = T>>(iter: I, k: usize) -> Option<usize> {
+    if k == 0 {
+        return Some(0);
+    }
+    let mut last_seen = HashMap::new();
+    // Where the longest run of distinct items ending at the current item starts
+    let mut run_start = 0;
+    for (i, item) in iter.into_iter().enumerate() {
+        if let Some(previous) = last_seen.insert(item, i) {
+            run_start = run_start.max(previous + 1);
+        }
+        if i + 1 - run_start >= k {
+            return Some(i + 1 - k);
+        }
+    }
+    None
+}
 
-pub trait IterTuple:Sized{
+/// A tuple of restartable iterators, which [`TupleProduct`] takes the cartesian product of
+pub trait IterTuple: Clone {
     type Items;
-    fn get_nexts(&mut self)->Option<Self::Items>;
-    fn iter_tuple_next(
-        source_iters: &mut Self,
-        active_iters: &mut Self,
-        current_values: &mut Option<Self::Items>
-    )->Option<Self::Items>;
+    /// The first item of every slot, or `None` if any slot is empty
+    fn first_items(&mut self) -> Option<Self::Items>;
+    /// Moves `current_values` on to the next combination, returning the one it held.
+    /// The first slot changes fastest, and each slot restarts from its source once it runs out
+    fn advance(sources: &Self, active_iters: &mut Self, current_values: &mut Option<Self::Items>) -> Option<Self::Items>;
+    /// Bounds on the combinations left after the current one
+    fn remaining_hint(sources: &Self, active_iters: &Self) -> (usize, Option<usize>);
+}
+/// An [`IterTuple`] where every slot knows its length, so any combination can be jumped to directly
+pub trait ExactIterTuple: IterTuple {
+    /// The number of combinations, or `None` if it overflows
+    fn product_len(&self) -> Option<usize>;
+    /// The active iterators and values as they are at the `index`th combination
+    fn positioned(sources: &Self, index: usize) -> Option<(Self, Self::Items)>;
 }
 
-macro_rules! impl_iter_tup {
-    (($l_type:ident,$l_value:ident,$l_source_iter:ident,$l_active_iter:ident)$(,($type:ident,$value:ident,$source_iter:ident,$active_iter:ident))*) => {
-        impl <$l_type:Iterator+Clone $(,$type:Iterator+Clone)*> IterTuple for ($l_type,$($type),*)
-            where <$l_type as Iterator>::Item:Clone $(,<$type as Iterator>::Item:Clone)*
+macro_rules! impl_iter_tuple {
+    ($(($T:ident, $i:tt)),+) => {
+        impl<$($T: Iterator + Clone),+> IterTuple for ($($T,)+)
+        where
+            $($T::Item: Clone),+
         {
-            type Items = (<$l_type as Iterator>::Item, $(<$type as Iterator>::Item),*);
-            fn get_nexts(&mut self)->Option<Self::Items>{
-                let ($l_source_iter,$($source_iter),*) = self;
-                Some(($l_source_iter.next()?,$($source_iter.next()?),*))
+            type Items = ($($T::Item,)+);
+            fn first_items(&mut self) -> Option<Self::Items> {
+                Some(($(self.$i.next()?,)+))
             }
-            fn iter_tuple_next(
-                source_iters: &mut Self,
-                active_iters: &mut Self,
-                current_values: &mut Option<Self::Items>
-            )->Option<Self::Items> {
-                let current_values_mut = current_values.as_mut()?;
-                let to_return = current_values_mut.clone();
-                let ($l_value,$($value),*) = current_values_mut;
-                let ($l_active_iter,$($active_iter),*) = active_iters;
-                let ($l_source_iter,$($source_iter),*) = source_iters;
-
-                if let Some(new_item) = $l_active_iter.next() { // If this slot can keep going
-                    *$l_value = new_item; // Update it
-                    return Some(to_return) // And return the previous_state
-                }else {
-                    *$l_active_iter = $l_source_iter.clone().into_iter(); // Restart the slot
-                    if let Some(replacement_item) = $l_active_iter.next() {
-                        *$l_value = replacement_item;// And store its first item
-                    } else { // If for some reason this slot now doesn't have any items
-                        *current_values = None; // Take away the current value
-                        return Some(to_return) // And return the last state
-                    }
-                }
-
+            fn advance(sources: &Self, active_iters: &mut Self, current_values: &mut Option<Self::Items>) -> Option<Self::Items> {
+                let values = current_values.as_mut()?;
+                let to_return = values.clone();
                 $(
-                    if let Some(new_item) = $active_iter.next() { // If this slot can keep going
-                        *$value = new_item; // Update it
-                        return Some(to_return) // And return the previous_state
-                    }else {
-                        *$active_iter = $source_iter.clone().into_iter(); // Restart the slot
-                        if let Some(replacement_item) = $active_iter.next() {
-                            *$value = replacement_item;// And store its first item
-                        } else { // If for some reason this slot now doesn't have any items
-                            *current_values = None; // Take away the current value
-                            return Some(to_return) // And return the last state
+                    if let Some(item) = active_iters.$i.next() { // If this slot can keep going
+                        values.$i = item;
+                        return Some(to_return);
+                    }
+                    active_iters.$i = sources.$i.clone(); // Otherwise restart it and carry on to the next slot
+                    match active_iters.$i.next() {
+                        Some(item) => values.$i = item,
+                        None => {
+                            *current_values = None;
+                            return Some(to_return);
                         }
                     }
-                )*
-                *current_values = None; // Take away the current value
-                return Some(to_return) // And return the last state
+                )+
+                // Every slot wrapped around, so that was the last combination
+                *current_values = None;
+                Some(to_return)
+            }
+            // The last slot's cycle length is never needed
+            #[allow(unused_assignments)]
+            fn remaining_hint(sources: &Self, active_iters: &Self) -> (usize, Option<usize>) {
+                // Each item left in a slot is worth a full cycle of all the faster slots before it
+                let (mut lower, mut upper) = (0usize, Some(0usize));
+                let (mut cycle_lower, mut cycle_upper) = (1usize, Some(1usize));
+                $(
+                    let (active_lower, active_upper) = active_iters.$i.size_hint();
+                    lower = lower.saturating_add(active_lower.saturating_mul(cycle_lower));
+                    upper = upper
+                        .zip(active_upper)
+                        .zip(cycle_upper)
+                        .and_then(|((upper, active), cycle)| upper.checked_add(active.checked_mul(cycle)?));
+                    let (source_lower, source_upper) = sources.$i.size_hint();
+                    cycle_lower = cycle_lower.saturating_mul(source_lower);
+                    cycle_upper = cycle_upper.zip(source_upper).and_then(|(cycle, source)| cycle.checked_mul(source));
+                )+
+                (lower, upper)
+            }
+        }
+        impl<$($T: ExactSizeIterator + Clone),+> ExactIterTuple for ($($T,)+)
+        where
+            $($T::Item: Clone),+
+        {
+            fn product_len(&self) -> Option<usize> {
+                Some(1usize)$(.and_then(|len| len.checked_mul(self.$i.len())))+
+            }
+            fn positioned(sources: &Self, mut index: usize) -> Option<(Self, Self::Items)> {
+                let mut active_iters = sources.clone();
+                let items = ($({
+                    let len = sources.$i.len().max(1);
+                    let item = active_iters.$i.nth(index % len)?;
+                    index /= len;
+                    item
+                },)+);
+                (index == 0).then_some((active_iters, items))
             }
         }
-        impl_iter_tup!($(($type,$value,$source_iter,$active_iter)),*);
     };
-    () => {
-    }
 }
+impl_iter_tuple!((A, 0));
+impl_iter_tuple!((A, 0), (B, 1));
+impl_iter_tuple!((A, 0), (B, 1), (C, 2));
+impl_iter_tuple!((A, 0), (B, 1), (C, 2), (D, 3));
+impl_iter_tuple!((A, 0), (B, 1), (C, 2), (D, 3), (E, 4));
+impl_iter_tuple!((A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5));
+impl_iter_tuple!((A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6));
+impl_iter_tuple!((A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7));
 
-impl_iter_tup!(
-    (A,a,a_source,a_active),
-    (B,b,b_source,b_active),
-    (C,c,c_source,c_active),
-    (D,d,d_source,d_active),
-    (E,e,e_source,e_active),
-    (F,f,f_source,f_active),
-    (G,g,g_source,g_active),
-    (H,h,h_source,h_active),
-    (I,i,i_source,i_active),
-    (J,j,j_source,j_active),
-    (K,k,k_source,k_active),
-    (L,l,l_source,l_active),
-    (M,m,m_source,m_active)
-);
-
-pub struct TupleProduct<T:IterTuple>{
-    source_iters:T,
-    active_iters:T,
-    current_values:Option<T::Items>,
+/// The cartesian product of a tuple of iterators with different item types, as tuples of their items.
+/// The first slot changes fastest
+#[derive(Debug, Clone)]
+pub struct TupleProduct<T: IterTuple> {
+    source_iters: T,
+    active_iters: T,
+    current_values: Option<T::Items>,
 }
-impl <T:IterTuple+Clone> TupleProduct<T>{
-    // pub fn new(source_iters:T)->Self{
-    //     let mut active_iters = source_iters.clone();
-    //     let current_values = active_iters.get_nexts();
-    //     Self { source_iters, active_iters, current_values }
-    // }
+impl<T: IterTuple> TupleProduct<T> {
+    #[allow(dead_code)]
+    pub fn new(source_iters: T) -> Self {
+        let mut active_iters = source_iters.clone();
+        let current_values = active_iters.first_items();
+        Self { source_iters, active_iters, current_values }
+    }
 }
-impl <T:IterTuple> Iterator for TupleProduct<T> {
+impl<T: ExactIterTuple> TupleProduct<T> {
+    /// The product from its `index`th combination onwards
+    pub fn starting_at(source_iters: T, index: usize) -> Self {
+        match T::positioned(&source_iters, index) {
+            Some((active_iters, current_values)) => Self {
+                source_iters,
+                active_iters,
+                current_values: Some(current_values),
+            },
+            None => Self {
+                active_iters: source_iters.clone(),
+                source_iters,
+                current_values: None,
+            },
+        }
+    }
+}
+impl<T: IterTuple> Iterator for TupleProduct<T> {
     type Item = T::Items;
     fn next(&mut self) -> Option<Self::Item> {
-        IterTuple::iter_tuple_next(
-            &mut self.source_iters,
-            &mut self.active_iters,
-            &mut self.current_values
-        )
+        T::advance(&self.source_iters, &mut self.active_iters, &mut self.current_values)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.current_values.is_none() {
+            return (0, Some(0));
+        }
+        let (lower, upper) = T::remaining_hint(&self.source_iters, &self.active_iters);
+        (lower.saturating_add(1), upper.and_then(|upper| upper.checked_add(1)))
     }
 }
+impl<T: ExactIterTuple> ExactSizeIterator for TupleProduct<T> {}
 
+/// A [`TupleProduct`] that rayon can split into ranges of combinations
+#[derive(Debug, Clone)]
+pub struct ParTupleProduct<T> {
+    source_iters: T,
+    start: usize,
+    end: usize,
+}
+impl<T: ExactIterTuple> ParTupleProduct<T> {
+    /// Panics if there are more combinations than fit in a `usize`
+    #[allow(dead_code)]
+    pub fn new(source_iters: T) -> Self {
+        let end = source_iters.product_len().expect("too many combinations to split");
+        Self { source_iters, start: 0, end }
+    }
+}
+impl<T: ExactIterTuple + Send> ParallelIterator for ParTupleProduct<T>
+where
+    T::Items: Send,
+{
+    type Item = T::Items;
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge_unindexed(self, consumer)
+    }
+}
+impl<T: ExactIterTuple + Send> UnindexedProducer for ParTupleProduct<T>
+where
+    T::Items: Send,
+{
+    type Item = T::Items;
+    fn split(self) -> (Self, Option<Self>) {
+        if self.end - self.start < 2 {
+            return (self, None);
+        }
+        let mid = self.start + (self.end - self.start) / 2;
+        let right = Self {
+            source_iters: self.source_iters.clone(),
+            start: mid,
+            end: self.end,
+        };
+        (Self { end: mid, ..self }, Some(right))
+    }
+    fn fold_with<F: Folder<Self::Item>>(self, folder: F) -> F {
+        let len = self.end - self.start;
+        folder.consume_iter(TupleProduct::starting_at(self.source_iters, self.start).take(len))
+    }
+}
+
+/// `n` choose `k`, saturating at `usize::MAX`
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    let k = k.min(n - k);
+    // Each partial product is itself a binomial coefficient, so the division is exact
+    (0..k).try_fold(1usize, |acc, i| Some(acc.checked_mul(n - i)? / (i + 1))).unwrap_or(usize::MAX)
+}
 
+/// Every way to choose `k` of `items`, each once whatever the order, as `Vec`s in the order the items came in
+#[allow(dead_code)]
+pub fn combinations_k<T: Clone>(items: &[T], k: usize) -> Combinations<'_, T> {
+    Combinations {
+        items,
+        indices: (0..k).collect(),
+        remaining: binomial(items.len(), k),
+    }
+}
+#[derive(Debug, Clone)]
+pub struct Combinations<'a, T> {
+    items: &'a [T],
+    indices: Vec<usize>,
+    remaining: usize,
+}
+impl<T: Clone> Iterator for Combinations<'_, T> {
+    type Item = Vec<T>;
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let combination = self.indices.iter().map(|&i| self.items[i].clone()).collect();
+        // Bump the last index that has room, and pack the ones after it straight after
+        let (n, k) = (self.items.len(), self.indices.len());
+        if let Some(i) = (0..k).rev().find(|&i| self.indices[i] < n - k + i) {
+            self.indices[i] += 1;
+            for j in i + 1..k {
+                self.indices[j] = self.indices[j - 1] + 1;
+            }
+        }
+        Some(combination)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+impl<T: Clone> ExactSizeIterator for Combinations<'_, T> {}
+
+/// Every ordering of every `k` distinct items (by position) of `items`
+#[allow(dead_code)]
+pub fn permutations_k<T: Clone>(items: &[T], k: usize) -> Permutations<'_, T> {
+    let n = items.len();
+    let remaining = if k > n {
+        0
+    } else {
+        (n - k + 1..=n).try_fold(1usize, |acc, i| acc.checked_mul(i)).unwrap_or(usize::MAX)
+    };
+    Permutations {
+        items,
+        indices: (0..n).collect(),
+        cycles: (n - k.min(n) + 1..=n).rev().collect(),
+        remaining,
+    }
+}
+#[derive(Debug, Clone)]
+pub struct Permutations<'a, T> {
+    items: &'a [T],
+    /// All the item indices, with the current permutation at the front
+    indices: Vec<usize>,
+    /// How many more items each position will take before it resets
+    cycles: Vec<usize>,
+    remaining: usize,
+}
+impl<T: Clone> Iterator for Permutations<'_, T> {
+    type Item = Vec<T>;
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let k = self.cycles.len();
+        let permutation = self.indices[..k].iter().map(|&i| self.items[i].clone()).collect();
+        let n = self.indices.len();
+        for i in (0..k).rev() {
+            self.cycles[i] -= 1;
+            if self.cycles[i] == 0 {
+                // Position `i` has had every remaining item, so put it back in order and move on to the one before
+                self.indices[i..].rotate_left(1);
+                self.cycles[i] = n - i;
+            } else {
+                let j = n - self.cycles[i];
+                self.indices.swap(i, j);
+                break;
+            }
+        }
+        Some(permutation)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+impl<T: Clone> ExactSizeIterator for Permutations<'_, T> {}
+
+/// Every subset of `items`, from the empty one up.
+/// Panics if there are too many items to count the subsets in a `usize`
+#[allow(dead_code)]
+pub fn subsets<T: Clone>(items: &[T]) -> impl ExactSizeIterator<Item = Vec<T>> + '_ {
+    assert!(items.len() < usize::BITS as usize, "too many subsets to count");
+    (0..1usize << items.len()).map(move |mask| {
+        items.iter().enumerate().filter(|(i, _)| mask & 1 << i != 0).map(|(_, item)| item.clone()).collect()
+    })
+}
+
+/// Every way to split `items` into two disjoint halves that cover them all, counting each split once.
+/// The first item is always in the left half.
+/// Panics if there are too many items to count the splits in a `usize`
+#[allow(dead_code)]
+pub fn partitions<T: Clone>(items: &[T]) -> impl ExactSizeIterator<Item = (Vec<T>, Vec<T>)> + '_ {
+    let rest = items.get(1..).unwrap_or_default();
+    assert!(rest.len() < usize::BITS as usize, "too many partitions to count");
+    (0..1usize << rest.len()).map(move |mask| {
+        let mut halves = (items.first().cloned().into_iter().collect::<Vec<_>>(), Vec::new());
+        for (i, item) in rest.iter().enumerate() {
+            let half = if mask & 1 << i != 0 { &mut halves.0 } else { &mut halves.1 };
+            half.push(item.clone());
+        }
+        halves
+    })
+}
+
+/// Every submask of `mask`, from `mask` itself down to 0
+pub fn submasks(mask: u64) -> impl Iterator<Item = u64> {
+    let mut next = Some(mask);
+    std::iter::from_fn(move || {
+        let submask = next?;
+        next = submask.checked_sub(1).map(|below| below & mask);
+        Some(submask)
+    })
+}
+
+/// Every split of the bits of `mask` into two disjoint masks, with the lowest bit always in the first
+pub fn mask_partitions(mask: u64) -> impl Iterator<Item = (u64, u64)> {
+    let lowest = mask & mask.wrapping_neg();
+    submasks(mask ^ lowest).map(move |submask| (submask | lowest, mask ^ (submask | lowest)))
+}
+
+#[allow(dead_code)]
 pub struct ArrayProduct<const N:usize, I:Iterator>{
     source_iters:[I;N],
     active_iters:[I;N],
     current_values:Option<[I::Item;N]>,
 }
 impl <const N:usize, I:Iterator+Clone> ArrayProduct<N,I> {
+    #[allow(dead_code)]
     pub fn new<T:IntoIterator<IntoIter = I>>(source_iters:[T;N])->Self
         where
     {
@@ -254,7 +505,12 @@ impl <const N:usize, I:Iterator+Clone> Iterator for ArrayProduct<N,I>
 }
 #[cfg(test)]
 mod test {
-    use super::ArrayProduct;
+    use rayon::iter::ParallelIterator;
+
+    use super::{
+        first_distinct_window, ArrayWindows, combinations_k, mask_partitions, partitions, permutations_k, subsets, ArrayProduct, ParTupleProduct,
+        TupleProduct,
+    };
     #[test]
     fn bools(){
         let a = [false,true].into_iter();
@@ -267,6 +523,64 @@ mod test {
     }
     #[test]
     fn tup(){
-        // dbg!(TupleProduct::new((std::iter::empty::<usize>(),IntoIterator::into_iter([0,1,2]),IntoIterator::into_iter([false,true]))).collect::<Vec<_>>());
+        let sources = (0..3usize, ['a', 'b'].into_iter(), [false, true].into_iter());
+        let mut product = TupleProduct::new(sources.clone());
+        assert_eq!(product.len(), 12);
+        assert_eq!(product.next(), Some((0, 'a', false)));
+        assert_eq!(product.next(), Some((1, 'a', false)));
+        assert_eq!(product.len(), 10);
+        let all: Vec<_> = TupleProduct::new(sources.clone()).collect();
+        assert_eq!(all.len(), 12);
+        assert_eq!(all[5], (2, 'b', false));
+        assert!(TupleProduct::starting_at(sources.clone(), 5).eq(all[5..].iter().copied()));
+
+        let mut parallel: Vec<_> = ParTupleProduct::new(sources).collect();
+        parallel.sort();
+        let mut sorted = all;
+        sorted.sort();
+        assert_eq!(parallel, sorted);
+        assert_eq!(TupleProduct::new((0..3, 0..0)).len(), 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn combinatorics() {
+        let items = ['a', 'b', 'c', 'd'];
+        let pairs: Vec<String> = combinations_k(&items, 2).map(String::from_iter).collect();
+        assert_eq!(pairs, ["ab", "ac", "ad", "bc", "bd", "cd"]);
+        assert_eq!(combinations_k(&items, 5).len(), 0);
+        assert_eq!(combinations_k(&items, 0).collect::<Vec<_>>(), [Vec::<char>::new()]);
+
+        let orders: Vec<String> = permutations_k(&items[..3], 2).map(String::from_iter).collect();
+        assert_eq!(orders, ["ab", "ac", "ba", "bc", "ca", "cb"]);
+        let mut all = permutations_k(&items, 4);
+        assert_eq!(all.len(), 24);
+        let mut seen: Vec<_> = all.by_ref().collect();
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 24);
+
+        assert_eq!(subsets(&items).len(), 16);
+        assert!(subsets(&items).any(|s| s == ['b', 'd']));
+        let splits: Vec<_> = partitions(&items[..3]).collect();
+        assert_eq!(splits.len(), 4);
+        assert!(splits.iter().all(|(left, right)| left[0] == 'a' && left.len() + right.len() == 3));
+
+        let mut mask_splits: Vec<_> = mask_partitions(0b1011).collect();
+        mask_splits.sort();
+        assert_eq!(mask_splits, [(0b0001, 0b1010), (0b0011, 0b1000), (0b1001, 0b0010), (0b1011, 0)]);
+    }
+
+    #[test]
+    fn windows() {
+        let windows: Vec<_> = ArrayWindows::<3, _>::new(0..5).collect();
+        assert_eq!(windows, [[0, 1, 2], [1, 2, 3], [2, 3, 4]]);
+        let mut short = ArrayWindows::<3, _>::new(0..2);
+        assert_eq!((short.size_hint(), short.next()), ((0, Some(0)), None));
+        assert_eq!(ArrayWindows::<2, _>::new(0..4).size_hint(), (3, Some(3)));
+
+        assert_eq!(first_distinct_window("mjqjpqmgbljsphdztnvjfqwrcgsmlb".chars(), 4), Some(3));
+        assert_eq!(first_distinct_window("mjqjpqmgbljsphdztnvjfqwrcgsmlb".chars(), 14), Some(5));
+        assert_eq!(first_distinct_window("aaaa".chars(), 2), None);
+        assert_eq!(first_distinct_window("ab".chars(), 0), Some(0));
+    }
+}
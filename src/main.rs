@@ -5,11 +5,16 @@ use std::env::args;
 mod array;
 mod astar;
 mod bitset;
+mod cycle;
 mod days;
+mod enums;
+mod graph;
 mod grid;
 mod iter;
 mod matcher;
+mod search;
 mod solution;
+mod stream;
 mod vec2;
 mod or;
 mod unzip;
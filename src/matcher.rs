@@ -1,4 +1,4 @@
-use std::{fmt::Debug, marker::PhantomData, str::FromStr, mem::MaybeUninit, convert::Infallible};
+use std::{fmt::{Debug, Display, Write}, marker::PhantomData, str::FromStr, mem::MaybeUninit, convert::Infallible};
 use crate::or::{self, Or};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -7,6 +7,16 @@ pub struct MatcherOutput<'s, T> {
     pub remaining: &'s str,
     pub chars_consumed: usize,
 }
+impl<'s, T> MatcherOutput<'s, T> {
+    #[inline]
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> MatcherOutput<'s, U> {
+        MatcherOutput {
+            matched: (f)(self.matched),
+            remaining: self.remaining,
+            chars_consumed: self.chars_consumed,
+        }
+    }
+}
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MatcherError<T> {
     pub index: usize,
@@ -47,12 +57,41 @@ pub trait Matcher {
     fn or<M:Matcher>(self,right:M)->OrMatcher<Self,M> where Self:Sized{
         OrMatcher::new(self, right)
     }
+    #[allow(dead_code)]
+    fn or_longest<M:Matcher>(self,right:M)->OrMatcher<Self,M> where Self:Sized{
+        OrMatcher::new(self, right).with_strategy(OrStrategy::LongestMatch)
+    }
+    #[allow(dead_code)]
+    fn or_all<M:Matcher>(self,right:M)->AllMatcher<Self,M> where Self:Sized{
+        AllMatcher::new(self, right)
+    }
     fn and<M:Matcher>(self,right:M)->AndMatcher<Self,M> where Self:Sized{
         AndMatcher::new(self, right)
     }
     fn many<C>(self)->ManyMatcher<Self,C> where Self:Sized{
         ManyMatcher::new(self)
     }
+    fn complete(self)->Complete<Self> where Self:Sized{
+        Complete::new(self)
+    }
+}
+
+/// The inverse of a [`Matcher`]: formats a value back into text the matcher would accept
+pub trait Printer<T: ?Sized> {
+    fn print<W: Write>(&self, value: &T, out: &mut W) -> std::fmt::Result;
+    #[allow(dead_code)]
+    fn print_to_string(&self, value: &T) -> String {
+        let mut string = String::new();
+        // Writing to a String cannot fail
+        let _ = self.print(value, &mut string);
+        string
+    }
+}
+
+/// A collection whose entries are either items or nested collections of the same type
+pub trait NestedList {
+    type Item;
+    fn entries(&self) -> impl Iterator<Item = Or<&Self::Item, &Self>>;
 }
 
 impl Matcher for char {
@@ -141,6 +180,8 @@ pub enum MatchNestedListErr<'s, C: Matcher, S: Matcher, I: Matcher> {
     ExpectedItemOrClose(I::Error<'s>, C::Error<'s>),
     // ExpectedSeparatorOrClose(S::Error<'s>, C::Error<'s>),
     ExpectedSeparator(S::Error<'s>),
+    /// More collections were open at once than the configured maximum depth
+    TooDeep(usize),
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ShortMatchNestedListErr {
@@ -149,6 +190,7 @@ pub enum ShortMatchNestedListErr {
     ExpectedItemOrClose,
     // ExpectedSeparatorOrClose,
     ExpectedSeparator,
+    TooDeep,
 }
 impl<C: Matcher, S: Matcher, I: Matcher> From<MatchNestedListErr<'_, C, S, I>>
     for ShortMatchNestedListErr
@@ -160,6 +202,7 @@ impl<C: Matcher, S: Matcher, I: Matcher> From<MatchNestedListErr<'_, C, S, I>>
             MatchNestedListErr::ExpectedItemOrClose(_, _) => Self::ExpectedItemOrClose,
             // MatchNestedListErr::ExpectedSeparatorOrClose(_, _) => Self::ExpectedSeparatorOrClose,
             MatchNestedListErr::ExpectedSeparator(_) => Self::ExpectedSeparator,
+            MatchNestedListErr::TooDeep(_) => Self::TooDeep,
         }
     }
 }
@@ -168,6 +211,7 @@ pub struct MatchNestedList<T, I, O, C, S> {
     close: C,
     separator: S,
     item: I,
+    max_depth: Option<usize>,
     phantom_collection: PhantomData<T>,
 }
 impl<T, I, O, C, S> MatchNestedList<T, I, O, C, S> {
@@ -177,9 +221,17 @@ impl<T, I, O, C, S> MatchNestedList<T, I, O, C, S> {
             close,
             separator,
             item,
+            max_depth: None,
             phantom_collection: PhantomData,
         }
     }
+    /// Fail with [`MatchNestedListErr::TooDeep`] rather than open more than `max_depth` collections at once
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        Self {
+            max_depth: Some(max_depth),
+            ..self
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -257,14 +309,29 @@ impl<A: Matcher, B: Matcher> Matcher for AndMatcher<A,B> {
 }
 
 
+/// How an [`OrMatcher`] picks between its alternatives
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OrStrategy {
+    /// Commit to the left alternative if it matches at all
+    #[default]
+    FirstMatch,
+    /// Try both alternatives, and keep whichever consumed more of the source (the left one on ties)
+    LongestMatch,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct OrMatcher<L, R> {
     left: L,
     right: R,
+    strategy: OrStrategy,
 }
 impl<L, R> OrMatcher<L, R> {
     pub fn new(left: L, right: R) -> Self {
-        Self { left, right }
+        Self { left, right, strategy: OrStrategy::FirstMatch }
+    }
+    #[allow(dead_code)]
+    pub fn with_strategy(self, strategy: OrStrategy) -> Self {
+        Self { strategy, ..self }
     }
 }
 impl<L: Matcher, R: Matcher> Matcher for OrMatcher<L, R> {
@@ -274,20 +341,97 @@ impl<L: Matcher, R: Matcher> Matcher for OrMatcher<L, R> {
         &mut self,
         source: &'s str,
     ) -> Result<MatcherOutput<'s, Self::Match<'s>>, MatcherError<Self::Error<'s>>> {
-        match self.left.next_match(source) {
-            Ok(l) => Ok(MatcherOutput {
-                matched: Or::Left(l.matched),
-                remaining: l.remaining,
-                chars_consumed: l.chars_consumed,
-            }),
-            Err(l_err) => match self.right.next_match(source) {
-                Ok(r) => Ok(MatcherOutput {
-                    matched: Or::Right(r.matched),
-                    remaining: r.remaining,
-                    chars_consumed: r.chars_consumed,
-                }),
-                Err(r_err) => Err(MatcherError::new(l_err.index, (l_err.error, r_err.error))),
-            },
+        let left = self.left.next_match(source);
+        let left = match (left, self.strategy) {
+            (Ok(l), OrStrategy::FirstMatch) => return Ok(l.map(Or::Left)),
+            (left, _) => left,
+        };
+        match (left, self.right.next_match(source)) {
+            (Ok(l), Ok(r)) => {
+                if r.remaining.len() < l.remaining.len() {
+                    Ok(r.map(Or::Right))
+                } else {
+                    Ok(l.map(Or::Left))
+                }
+            }
+            (Ok(l), Err(_)) => Ok(l.map(Or::Left)),
+            (Err(_), Ok(r)) => Ok(r.map(Or::Right)),
+            (Err(l_err), Err(r_err)) => Err(MatcherError::new(l_err.index, (l_err.error, r_err.error))),
+        }
+    }
+}
+
+/// Tries both alternatives, keeping every one that matches, for ambiguous grammars.
+///
+/// Each alternative carries its own remaining source to continue from.
+/// The output as a whole continues after the longest alternative
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllMatcher<L, R> {
+    left: L,
+    right: R,
+}
+impl<L, R> AllMatcher<L, R> {
+    pub fn new(left: L, right: R) -> Self {
+        Self { left, right }
+    }
+}
+impl<L: Matcher, R: Matcher> Matcher for AllMatcher<L, R> {
+    type Match<'m> = Vec<MatcherOutput<'m, Or<L::Match<'m>, R::Match<'m>>>>;
+    type Error<'e> = (L::Error<'e>, R::Error<'e>);
+    fn next_match<'s>(
+        &mut self,
+        source: &'s str,
+    ) -> Result<MatcherOutput<'s, Self::Match<'s>>, MatcherError<Self::Error<'s>>> {
+        let (matches, l_err, r_err) = match (self.left.next_match(source), self.right.next_match(source)) {
+            (Ok(l), Ok(r)) => (vec![l.map(Or::Left), r.map(Or::Right)], None, None),
+            (Ok(l), Err(r_err)) => (vec![l.map(Or::Left)], None, Some(r_err)),
+            (Err(l_err), Ok(r)) => (vec![r.map(Or::Right)], Some(l_err), None),
+            (Err(l_err), Err(r_err)) => (vec![], Some(l_err), Some(r_err)),
+        };
+        let Some(longest) = matches.iter().min_by_key(|m| m.remaining.len()) else {
+            let (Some(l_err), Some(r_err)) = (l_err, r_err) else {
+                unreachable!("Both alternatives failed")
+            };
+            return Err(MatcherError::new(l_err.index, (l_err.error, r_err.error)));
+        };
+        Ok(MatcherOutput {
+            remaining: longest.remaining,
+            chars_consumed: longest.chars_consumed,
+            matched: matches,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompleteErr<'e, E> {
+    Matcher(E),
+    RemainingInput(&'e str),
+}
+/// Fails if the inner matcher leaves any of the source unmatched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Complete<M> {
+    matcher: M,
+}
+impl<M> Complete<M> {
+    pub fn new(matcher: M) -> Self {
+        Self { matcher }
+    }
+}
+impl<M: Matcher> Matcher for Complete<M> {
+    type Match<'m> = M::Match<'m>;
+    type Error<'e> = CompleteErr<'e, M::Error<'e>>;
+    fn next_match<'s>(
+        &mut self,
+        source: &'s str,
+    ) -> Result<MatcherOutput<'s, Self::Match<'s>>, MatcherError<Self::Error<'s>>> {
+        let output = self.matcher.next_match(source).map_err(|e| e.map(CompleteErr::Matcher))?;
+        if output.remaining.is_empty() {
+            Ok(output)
+        } else {
+            Err(MatcherError::new(
+                output.chars_consumed,
+                CompleteErr::RemainingInput(output.remaining),
+            ))
         }
     }
 }
@@ -400,6 +544,12 @@ where
                 ..
             }) = self.open.next_match(remaining_string)
             {
+                if let Some(max_depth) = self.max_depth.filter(|&max| collections.len() >= max) {
+                    return Err(MatcherError::new(
+                        total_chars_consumed,
+                        MatchNestedListErr::TooDeep(max_depth),
+                    ));
+                }
                 remaining_string = remaining;
                 total_chars_consumed += chars_consumed;
                 collections.push(T::default())
@@ -478,6 +628,7 @@ where
 pub struct Delimeted<C,I,D>{
     item:I,
     delimeter:D,
+    exhaustive:bool,
     phantom_collection:PhantomData<C>
 }
 impl<I, D, C> Delimeted<C,I,D> {
@@ -485,9 +636,15 @@ impl<I, D, C> Delimeted<C,I,D> {
         Self {
             delimeter,
             item,
+            exhaustive: false,
             phantom_collection: PhantomData,
         }
     }
+    /// Instead of stopping at the first missing delimeter, fail with [`DelimetedError::ExpectedDelimeter`]
+    /// unless the whole source was matched
+    pub fn exhaustive(self) -> Self {
+        Self { exhaustive: true, ..self }
+    }
 }
 
 #[derive(Debug)]
@@ -518,8 +675,12 @@ impl <I:Matcher+Debug,D:Matcher+Debug,C:Default+for<'a> Extend<I::Match<'a>>> Ma
 
         loop {
             // println!("Test {:?}",collection);
-            let Ok(next_delim) = self.delimeter.next_match(remaining).map_err(|e|e.map(DelimetedError::<I,D>::ExpectedDelimeter).with_offset(chars_consumed)) else {
-                break;
+            let next_delim = match self.delimeter.next_match(remaining) {
+                Ok(next_delim) => next_delim,
+                Err(e) if self.exhaustive && !remaining.is_empty() => {
+                    return Err(e.map(DelimetedError::ExpectedDelimeter).with_offset(chars_consumed));
+                }
+                Err(_) => break,
             };
             chars_consumed+=next_delim.chars_consumed;
             remaining = next_delim.remaining;
@@ -592,10 +753,8 @@ impl <const N:usize,I:Matcher+Debug,D:Matcher+Debug> Matcher for DelimetedArray<
             remaining = first_item.remaining;
             chars_consumed = first_item.chars_consumed;
             for (index,item) in item_iter {
-                let Ok(next_delim) = self.delimeter.next_match(remaining).map_err(|e|e.map(DelimetedArrayError::<N,I,D>::ExpectedDelimeter).with_offset(chars_consumed)) else {
-                    break;
-                };
-                
+                let next_delim = self.delimeter.next_match(remaining).map_err(|e|e.map(DelimetedArrayError::<N,I,D>::ExpectedDelimeter).with_offset(chars_consumed))?;
+
                 chars_consumed+=next_delim.chars_consumed;
                 remaining = next_delim.remaining;
 
@@ -616,4 +775,154 @@ impl <const N:usize,I:Matcher+Debug,D:Matcher+Debug> Matcher for DelimetedArray<
             chars_consumed,
         })
     }
-}
\ No newline at end of file
+}
+
+// Literals print themselves, whatever value they are paired with
+impl<T: ?Sized> Printer<T> for char {
+    fn print<W: Write>(&self, _: &T, out: &mut W) -> std::fmt::Result {
+        out.write_char(*self)
+    }
+}
+impl<T: ?Sized> Printer<T> for &str {
+    fn print<W: Write>(&self, _: &T, out: &mut W) -> std::fmt::Result {
+        out.write_str(self)
+    }
+}
+impl<T: ?Sized, P: Printer<T>> Printer<T> for &mut P {
+    fn print<W: Write>(&self, value: &T, out: &mut W) -> std::fmt::Result {
+        P::print(self, value, out)
+    }
+}
+impl<M: Display> Printer<M> for FromStrMatcher<M> {
+    fn print<W: Write>(&self, value: &M, out: &mut W) -> std::fmt::Result {
+        write!(out, "{value}")
+    }
+}
+impl<TA, TB, A: Printer<TA>, B: Printer<TB>> Printer<(TA, TB)> for AndMatcher<A, B> {
+    fn print<W: Write>(&self, (a, b): &(TA, TB), out: &mut W) -> std::fmt::Result {
+        self.a.print(a, out)?;
+        self.b.print(b, out)
+    }
+}
+impl<TL, TR, L: Printer<TL>, R: Printer<TR>> Printer<Or<TL, TR>> for OrMatcher<L, R> {
+    fn print<W: Write>(&self, value: &Or<TL, TR>, out: &mut W) -> std::fmt::Result {
+        match value {
+            Or::Left(l) => self.left.print(l, out),
+            Or::Right(r) => self.right.print(r, out),
+        }
+    }
+}
+impl<T, M: Printer<T>> Printer<T> for Complete<M> {
+    fn print<W: Write>(&self, value: &T, out: &mut W) -> std::fmt::Result {
+        self.matcher.print(value, out)
+    }
+}
+impl<T, C, M: Printer<T>> Printer<C> for ManyMatcher<M, C>
+where
+    for<'c> &'c C: IntoIterator<Item = &'c T>,
+{
+    fn print<W: Write>(&self, collection: &C, out: &mut W) -> std::fmt::Result {
+        collection.into_iter().try_for_each(|item| self.matcher.print(item, out))
+    }
+}
+impl<T, C, I: Printer<T>, D: Printer<()>> Printer<C> for Delimeted<C, I, D>
+where
+    for<'c> &'c C: IntoIterator<Item = &'c T>,
+{
+    fn print<W: Write>(&self, collection: &C, out: &mut W) -> std::fmt::Result {
+        for (index, item) in collection.into_iter().enumerate() {
+            if index != 0 {
+                self.delimeter.print(&(), out)?;
+            }
+            self.item.print(item, out)?;
+        }
+        Ok(())
+    }
+}
+impl<const N: usize, T, I: Printer<T>, D: Printer<()>> Printer<[T; N]> for DelimetedArray<N, I, D> {
+    fn print<W: Write>(&self, items: &[T; N], out: &mut W) -> std::fmt::Result {
+        for (index, item) in items.iter().enumerate() {
+            if index != 0 {
+                self.delimeter.print(&(), out)?;
+            }
+            self.item.print(item, out)?;
+        }
+        Ok(())
+    }
+}
+impl<T: NestedList, I: Printer<T::Item>, O: Printer<()>, C: Printer<()>, S: Printer<()>> Printer<T>
+    for MatchNestedList<T, I, O, C, S>
+{
+    fn print<W: Write>(&self, list: &T, out: &mut W) -> std::fmt::Result {
+        // The entries left in each open list, and whether it has printed any yet, so nesting depth doesn't use the call stack
+        self.open.print(&(), out)?;
+        let mut open_lists = vec![(list.entries(), false)];
+        while let Some((entries, printed_any)) = open_lists.last_mut() {
+            let Some(entry) = entries.next() else {
+                open_lists.pop();
+                self.close.print(&(), out)?;
+                continue;
+            };
+            if std::mem::replace(printed_any, true) {
+                self.separator.print(&(), out)?;
+            }
+            match entry {
+                Or::Left(item) => self.item.print(item, out)?,
+                Or::Right(nested) => {
+                    self.open.print(&(), out)?;
+                    open_lists.push((nested.entries(), false));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompleteErr, Delimeted, DelimetedArray, DelimetedArrayError, DelimetedError, FromStrMatcher, Matcher};
+    use crate::or::Or;
+
+    #[test]
+    fn or_strategies() {
+        let first = "ab".or("abc").next_match("abcd").unwrap();
+        assert_eq!(first.matched, Or::Left("ab"));
+        let longest = "ab".or_longest("abc").next_match("abcd").unwrap();
+        assert_eq!(longest.matched, Or::Right("abc"));
+        assert_eq!(longest.remaining, "d");
+
+        let all = "ab".or_all("abc").next_match("abcd").unwrap();
+        assert_eq!(all.matched.iter().map(|m| m.remaining).collect::<Vec<_>>(), ["cd", "d"]);
+        assert_eq!(all.remaining, "d");
+        assert!("x".or_all("y").next_match("z").is_err());
+    }
+
+    #[test]
+    fn complete() {
+        let mut number = FromStrMatcher::<u32>::MATCHER.complete();
+        assert_eq!(number.next_match("123").unwrap().matched, 123);
+        let err = number.next_match("12a").unwrap_err();
+        assert_eq!((err.index, err.error), (2, CompleteErr::RemainingInput("a")));
+    }
+
+    #[test]
+    fn exhaustive_delimeted() {
+        let mut lenient = Delimeted::<Vec<u8>, _, _>::new(FromStrMatcher::<u8>::MATCHER, ',');
+        let lenient = lenient.next_match("1,2;3").unwrap();
+        assert_eq!((lenient.matched, lenient.remaining), (vec![1, 2], ";3"));
+
+        let mut strict = Delimeted::<Vec<u8>, _, _>::new(FromStrMatcher::<u8>::MATCHER, ',').exhaustive();
+        assert_eq!(strict.next_match("1,2,3").unwrap().matched, [1, 2, 3]);
+        let err = strict.next_match("1,2;3").unwrap_err();
+        assert_eq!(err.index, 3);
+        assert!(matches!(err.error, DelimetedError::ExpectedDelimeter(Some(';'))));
+    }
+    #[test]
+    fn array_missing_delimeter() {
+        let mut triple = DelimetedArray::<3, _, _>::new(FromStrMatcher::<u8>::MATCHER, ',');
+        assert_eq!(triple.next_match("1,2,3").unwrap().matched, [1, 2, 3]);
+        let err = triple.next_match("1,2;3").unwrap_err();
+        assert_eq!(err.index, 3);
+        assert!(matches!(err.error, DelimetedArrayError::ExpectedDelimeter(Some(';'))));
+    }
+}
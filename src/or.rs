@@ -25,6 +25,7 @@ impl<L, R> Or<L, R> {
     //         Or::Right(new_l) => Or::Left(new_l),
     //     }
     // }
+    #[allow(dead_code)]
     pub fn map_both<T,U,F:FnOnce(L)->T,G:FnOnce(R)->U>(self,f:F,g:G)->Or<T,U>{
         match self {
             Or::Left(l) => Or::Left(f(l)),
@@ -45,6 +46,7 @@ impl <T> Or<T,T> {
 }
 
 impl <L:IntoIterator,R:IntoIterator<Item=L::Item>> Or<L,R> {
+    #[allow(dead_code)]
     pub fn iter_collapsed(self)->CollapsedOrIter<L::IntoIter,R::IntoIter>{
         CollapsedOrIter(self.map_both(IntoIterator::into_iter, IntoIterator::into_iter))
     }
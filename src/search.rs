@@ -0,0 +1,179 @@
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, HashMap},
+    hash::Hash,
+};
+
+/// Decides which states a [`BranchAndBound`] search can skip as already seen
+pub trait CanonicalKey<S> {
+    type Key: Hash + Eq;
+    fn key(&self, state: &S) -> Option<Self::Key>;
+}
+/// Explores every state, without memoisation
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoMemo;
+impl<S> CanonicalKey<S> for NoMemo {
+    type Key = ();
+    fn key(&self, _: &S) -> Option<()> {
+        None
+    }
+}
+impl<S, K: Hash + Eq, F: Fn(&S) -> K> CanonicalKey<S> for F {
+    type Key = K;
+    fn key(&self, state: &S) -> Option<K> {
+        Some(self(state))
+    }
+}
+/// Memoises only the states the closure gives a key for, exploring every other state
+#[derive(Debug, Clone, Copy)]
+pub struct PartialKey<F>(pub F);
+impl<S, K: Hash + Eq, F: Fn(&S) -> Option<K>> CanonicalKey<S> for PartialKey<F> {
+    type Key = K;
+    fn key(&self, state: &S) -> Option<K> {
+        (self.0)(state)
+    }
+}
+
+/// Searches for the state with the highest score, skipping any branch whose `upper_bound` can't beat the best score so far.
+///
+/// `score` is the value of stopping at a state, and `upper_bound` must never be below the score of the state or any of its descendants.
+/// Without a beam width the search is an exact depth-first search, trying children with the highest bound first.
+#[derive(Debug, Clone)]
+pub struct BranchAndBound<N, F, U, M = NoMemo> {
+    get_children: N,
+    score: F,
+    upper_bound: U,
+    canonical_key: M,
+    beam_width: Option<usize>,
+}
+impl<N, F, U> BranchAndBound<N, F, U> {
+    pub fn new(get_children: N, score: F, upper_bound: U) -> Self {
+        Self {
+            get_children,
+            score,
+            upper_bound,
+            canonical_key: NoMemo,
+            beam_width: None,
+        }
+    }
+}
+impl<N, F, U, M> BranchAndBound<N, F, U, M> {
+    /// Skips a state if another state with the same key has already been seen with at least its score.
+    /// The key has to capture everything about a state that affects what its descendants can add to the score
+    pub fn memoize<K>(self, canonical_key: K) -> BranchAndBound<N, F, U, K> {
+        BranchAndBound {
+            get_children: self.get_children,
+            score: self.score,
+            upper_bound: self.upper_bound,
+            canonical_key,
+            beam_width: self.beam_width,
+        }
+    }
+    /// Searches breadth first instead, keeping only the `beam_width` states with the highest bounds at each depth.
+    /// Much faster, but the best state can be missed if the bound is a poor guide
+    #[allow(dead_code)]
+    pub fn beam_width(mut self, beam_width: usize) -> Self {
+        self.beam_width = Some(beam_width);
+        self
+    }
+    /// Records a newly reached state, returning whether its children are worth exploring
+    fn visit<S: Clone, V: Ord + Clone>(&self, state: &S, best: &mut Option<(V, S)>, seen: &mut HashMap<M::Key, V>) -> bool
+    where
+        F: Fn(&S) -> V,
+        U: Fn(&S) -> V,
+        M: CanonicalKey<S>,
+    {
+        let score = (self.score)(state);
+        if let Some(key) = self.canonical_key.key(state) {
+            match seen.entry(key) {
+                Entry::Occupied(o) if o.get() >= &score => return false,
+                Entry::Occupied(mut o) => {
+                    o.insert(score.clone());
+                }
+                Entry::Vacant(v) => {
+                    v.insert(score.clone());
+                }
+            }
+        }
+        if best.as_ref().is_none_or(|(best_score, _)| &score > best_score) {
+            *best = Some((score, state.clone()));
+        }
+        best.as_ref().is_some_and(|(best_score, _)| &(self.upper_bound)(state) > best_score)
+    }
+    /// The best score reachable from the initial states, and the state that reaches it
+    pub fn maximize<S, V, Ns, Ss>(&self, initial_states: Ss) -> Option<(V, S)>
+    where
+        S: Clone,
+        V: Ord + Clone,
+        N: Fn(&S) -> Ns,
+        Ns: IntoIterator<Item = S>,
+        F: Fn(&S) -> V,
+        U: Fn(&S) -> V,
+        M: CanonicalKey<S>,
+        Ss: IntoIterator<Item = S>,
+    {
+        let mut best = None;
+        let mut seen = HashMap::new();
+        match self.beam_width {
+            None => {
+                let mut states_to_visit: Vec<S> = initial_states.into_iter().collect();
+                while let Some(state) = states_to_visit.pop() {
+                    if !self.visit(&state, &mut best, &mut seen) {
+                        continue;
+                    }
+                    let mut children: Vec<_> = (self.get_children)(&state)
+                        .into_iter()
+                        .map(|child| ((self.upper_bound)(&child), child))
+                        .collect();
+                    // The last child is popped first, so the most promising goes on top
+                    children.sort_by(|a, b| a.0.cmp(&b.0));
+                    states_to_visit.extend(children.into_iter().map(|(_, child)| child));
+                }
+            }
+            Some(beam_width) => {
+                let mut layer: Vec<S> = initial_states.into_iter().collect();
+                while !layer.is_empty() {
+                    let mut next_layer: Vec<_> = layer
+                        .iter()
+                        .filter(|state| self.visit(state, &mut best, &mut seen))
+                        .flat_map(|state| (self.get_children)(state))
+                        .map(|child| ((self.upper_bound)(&child), child))
+                        .collect();
+                    if next_layer.len() > beam_width {
+                        next_layer.select_nth_unstable_by_key(beam_width, |(bound, _)| Reverse(bound.clone()));
+                        next_layer.truncate(beam_width);
+                    }
+                    layer = next_layer.into_iter().map(|(_, child)| child).collect();
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BranchAndBound;
+
+    #[test]
+    fn knapsack() {
+        // (weight, value), with a capacity of 10
+        const ITEMS: [(u32, u32); 5] = [(5, 10), (4, 40), (6, 30), (3, 50), (2, 5)];
+        // A state is the next item to consider, the weight used and the value taken
+        let children = |&(next, weight, value): &(usize, u32, u32)| {
+            let skip = (next < ITEMS.len()).then_some((next + 1, weight, value));
+            let take = ITEMS
+                .get(next)
+                .filter(|item| weight + item.0 <= 10)
+                .map(|item| (next + 1, weight + item.0, value + item.1));
+            skip.into_iter().chain(take)
+        };
+        let bound = |&(next, _, value): &(usize, u32, u32)| value + ITEMS[next..].iter().map(|i| i.1).sum::<u32>();
+        let search = BranchAndBound::new(children, |s: &(usize, u32, u32)| s.2, bound);
+        assert_eq!(search.maximize([(0, 0, 0)]).map(|b| b.0), Some(95));
+
+        let memoized = search.clone().memoize(|s: &(usize, u32, u32)| (s.0, s.1));
+        assert_eq!(memoized.maximize([(0, 0, 0)]), search.maximize([(0, 0, 0)]));
+        assert_eq!(search.beam_width(64).maximize([(0, 0, 0)]).map(|b| b.0), Some(95));
+    }
+}
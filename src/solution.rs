@@ -1,15 +1,27 @@
 use std::{
     fmt::{Debug, Display},
+    fs::File,
+    io::{BufRead, BufReader},
     path::PathBuf,
     str::FromStr,
 };
 
+use crate::or::Or;
+
 pub trait AOCSolution {
     type Input: FromStr;
     type Part1: Display;
     type Part2: Display;
     type Err;
     fn solve(input: Self::Input) -> Result<(Self::Part1, Self::Part2), Self::Err>;
+    /// Reads and parses the input. Days that can parse their input incrementally override this
+    fn read_input<R: BufRead>(
+        mut reader: R,
+    ) -> Result<Self::Input, Or<std::io::Error, <Self::Input as FromStr>::Err>> {
+        let mut input_str = String::new();
+        reader.read_to_string(&mut input_str).map_err(Or::Left)?;
+        input_str.parse().map_err(Or::Right)
+    }
 }
 
 #[derive(Debug)]
@@ -38,13 +50,14 @@ where
         Some(p) => p,
         _ => return "Cannot find inputs folder".into(),
     };
-    let input_str = match std::fs::read_to_string(input_path) {
-        Ok(s) => s,
+    let input_file = match File::open(input_path) {
+        Ok(f) => f,
         Err(e) => return format!("Error reading input for day {day_index}: {e:?}"),
     };
-    let input: S::Input = match input_str.parse() {
+    let input: S::Input = match S::read_input(BufReader::new(input_file)) {
         Ok(i) => i,
-        Err(e) => return format!("Error parsing input for day {day_index}: {e:?}"),
+        Err(Or::Left(e)) => return format!("Error reading input for day {day_index}: {e:?}"),
+        Err(Or::Right(e)) => return format!("Error parsing input for day {day_index}: {e:?}"),
     };
     let (part_1, part_2) = match S::solve(input) {
         Ok(s) => s,
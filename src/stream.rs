@@ -0,0 +1,226 @@
+use std::io::{self, BufRead};
+
+use crate::matcher::{Matcher, MatcherError};
+
+#[derive(Debug)]
+pub enum StreamError<I, D> {
+    Io(io::Error),
+    ExpectedItem(MatcherError<I>),
+    ExpectedDelimeter(MatcherError<D>),
+}
+
+/// Drives an item matcher (separated by a delimeter matcher) over a [`BufRead`] source,
+/// only keeping the unmatched tail of the input in memory.
+///
+/// A match is only accepted once it stops short of the end of the buffer (or the source is exhausted),
+/// so items split across reads are re-matched once more input arrives.
+/// Likewise an error is only reported early once it can't be down to a truncated read, see [`is_conclusive`].
+/// A trailing delimeter at the end of the source is allowed.
+pub struct MatchStream<R, I, D> {
+    reader: R,
+    item: I,
+    delimeter: D,
+    buffer: String,
+    undecoded: Vec<u8>,
+    consumed: usize,
+    chars_consumed: usize,
+    /// How far past `consumed` a delimeter has been ruled out, see [`is_conclusive`]
+    delimeter_scanned: usize,
+    expect_delimeter: bool,
+    eof: bool,
+    done: bool,
+}
+impl<R, I, D> MatchStream<R, I, D> {
+    pub fn new(reader: R, item: I, delimeter: D) -> Self {
+        Self {
+            reader,
+            item,
+            delimeter,
+            buffer: String::new(),
+            undecoded: Vec::new(),
+            consumed: 0,
+            chars_consumed: 0,
+            delimeter_scanned: 0,
+            expect_delimeter: false,
+            eof: false,
+            done: false,
+        }
+    }
+}
+impl<R: BufRead, I, D> MatchStream<R, I, D> {
+    /// Appends the next chunk of the reader to the buffer, dropping the already-consumed prefix
+    fn fill(&mut self) -> io::Result<()> {
+        self.buffer.drain(..self.consumed);
+        self.consumed = 0;
+
+        let chunk = self.reader.fill_buf()?;
+        if chunk.is_empty() {
+            self.eof = true;
+            return if self.undecoded.is_empty() {
+                Ok(())
+            } else {
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "stream ended in the middle of a character",
+                ))
+            };
+        }
+        self.undecoded.extend_from_slice(chunk);
+        let chunk_len = chunk.len();
+        self.reader.consume(chunk_len);
+
+        // Only decode up to the last complete character, the rest waits for the next chunk
+        let valid_len = match std::str::from_utf8(&self.undecoded) {
+            Ok(s) => s.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+        if let Ok(decoded) = std::str::from_utf8(&self.undecoded[..valid_len]) {
+            self.buffer.push_str(decoded);
+        }
+        self.undecoded.drain(..valid_len);
+        Ok(())
+    }
+}
+/// Whether an error `index` chars into `source` is final, rather than down to the read stopping early.
+/// The failing position has to be inside the buffer, and for items followed by a `delimeter` so the whole item has been seen
+/// (matchers like [`FromStrMatcher`](crate::matcher::FromStrMatcher) can't pinpoint where they went wrong).
+/// The delimeter comes with how many bytes of `source` it's already been ruled out of, which this moves on,
+/// so waiting on a long item only looks at each new chunk once
+fn is_conclusive<D: Matcher>(source: &str, index: usize, delimeter: Option<(&mut D, &mut usize)>) -> bool {
+    let Some((start, _)) = source.char_indices().nth(index) else {
+        return false;
+    };
+    delimeter.is_none_or(|(delimeter, scanned)| {
+        let from = start.max(*scanned);
+        let mut ruled_out = true;
+        for (offset, char) in source[from..].char_indices() {
+            let rest = &source[from + offset..];
+            match delimeter.next_match(rest) {
+                Ok(_) => return true,
+                // Failing before the end of the buffer won't change once more arrives
+                Err(e) if ruled_out && rest.chars().nth(e.index).is_some() => {
+                    *scanned = from + offset + char.len_utf8();
+                }
+                Err(_) => ruled_out = false,
+            }
+        }
+        false
+    })
+}
+
+impl<R: BufRead, I, D, T, IE, DE> Iterator for MatchStream<R, I, D>
+where
+    I: for<'s> Matcher<Match<'s> = T, Error<'s> = IE>,
+    D: for<'s> Matcher<Error<'s> = DE>,
+{
+    type Item = Result<T, StreamError<IE, DE>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            let source = &self.buffer[self.consumed..];
+            if self.expect_delimeter {
+                match self.delimeter.next_match(source) {
+                    Ok(delim) if self.eof || !delim.remaining.is_empty() => {
+                        self.consumed += source.len() - delim.remaining.len();
+                        self.delimeter_scanned = 0;
+                        self.chars_consumed += delim.chars_consumed;
+                        self.expect_delimeter = false;
+                        continue;
+                    }
+                    Err(e) if self.eof || is_conclusive::<D>(source, e.index, None) => {
+                        self.done = true;
+                        return (!source.is_empty()).then(|| {
+                            Err(StreamError::ExpectedDelimeter(
+                                e.with_offset(self.chars_consumed),
+                            ))
+                        });
+                    }
+                    _ => {}
+                }
+            } else {
+                match self.item.next_match(source) {
+                    Ok(item) if self.eof || !item.remaining.is_empty() => {
+                        self.consumed += source.len() - item.remaining.len();
+                        self.delimeter_scanned = 0;
+                        self.chars_consumed += item.chars_consumed;
+                        self.expect_delimeter = true;
+                        return Some(Ok(item.matched));
+                    }
+                    Err(e) if self.eof || is_conclusive(source, e.index, Some((&mut self.delimeter, &mut self.delimeter_scanned))) => {
+                        self.done = true;
+                        return (!source.is_empty())
+                            .then(|| Err(StreamError::ExpectedItem(e.with_offset(self.chars_consumed))));
+                    }
+                    _ => {}
+                }
+            }
+            if let Err(e) = self.fill() {
+                self.done = true;
+                return Some(Err(StreamError::Io(e)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, io::BufReader};
+
+    use super::{MatchStream, StreamError};
+    use crate::matcher::{FromStrMatcher, Matcher, MatcherError, MatcherOutput};
+
+    #[test]
+    fn items_across_chunks() {
+        let source = "12\n-345\n6789\n0\n";
+        let reader = BufReader::with_capacity(3, source.as_bytes());
+        let numbers = MatchStream::new(reader, FromStrMatcher::<i32>::MATCHER, '\n')
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(numbers, [12, -345, 6789, 0]);
+    }
+
+    #[test]
+    fn bad_item() {
+        let reader = BufReader::with_capacity(2, "1\n2\nx\n".as_bytes());
+        let mut stream = MatchStream::new(reader, FromStrMatcher::<u8>::MATCHER, '\n');
+        assert!(matches!(stream.next(), Some(Ok(1))));
+        assert!(matches!(stream.next(), Some(Ok(2))));
+        assert!(matches!(stream.next(), Some(Err(StreamError::ExpectedItem(e))) if e.index == 4));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn bad_item_reported_early() {
+        let source = format!("1\n-\n{}", "2\n".repeat(10_000));
+        let reader = BufReader::with_capacity(4, source.as_bytes());
+        let mut stream = MatchStream::new(reader, FromStrMatcher::<i8>::MATCHER, '\n');
+        assert!(matches!(stream.next(), Some(Ok(1))));
+        assert!(matches!(stream.next(), Some(Err(StreamError::ExpectedItem(e))) if e.index == 2));
+        assert!(stream.next().is_none());
+        // Only the chunk holding the bad item was read
+        assert!(stream.reader.get_ref().len() > 19_000);
+    }
+    /// A delimeter that counts how many times it's tried
+    struct CountedNewline<'c>(&'c Cell<usize>);
+    impl Matcher for CountedNewline<'_> {
+        type Match<'m> = ();
+        type Error<'e> = Option<char>;
+        fn next_match<'s>(&mut self, source: &'s str) -> Result<MatcherOutput<'s, ()>, MatcherError<Option<char>>> {
+            self.0.set(self.0.get() + 1);
+            '\n'.next_match(source)
+        }
+    }
+
+    #[test]
+    fn long_bad_item_scanned_once() {
+        let source = format!("x{}", "y".repeat(20_000));
+        let tries = Cell::new(0);
+        let reader = BufReader::with_capacity(16, source.as_bytes());
+        let mut stream = MatchStream::new(reader, FromStrMatcher::<u8>::MATCHER, CountedNewline(&tries));
+        assert!(matches!(stream.next(), Some(Err(StreamError::ExpectedItem(e))) if e.index == 0));
+        assert!(tries.get() <= source.len(), "{} tries", tries.get());
+    }
+}
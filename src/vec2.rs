@@ -1,6 +1,6 @@
 use std::{
     fmt::Debug,
-    ops::{Add, AddAssign, Sub, SubAssign},
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
 };
 
 #[derive(Hash, Default, Clone, Copy, PartialEq, Eq)]
@@ -18,6 +18,76 @@ impl<T> Vec2<T> {
         Self { x, y }
     }
 }
+impl<T> From<(T, T)> for Vec2<T> {
+    fn from((x, y): (T, T)) -> Self {
+        Self { x, y }
+    }
+}
+impl<T> From<Vec2<T>> for (T, T) {
+    fn from(Vec2 { x, y }: Vec2<T>) -> Self {
+        (x, y)
+    }
+}
+impl<T> From<[T; 2]> for Vec2<T> {
+    fn from([x, y]: [T; 2]) -> Self {
+        Self { x, y }
+    }
+}
+impl<T> From<Vec2<T>> for [T; 2] {
+    fn from(Vec2 { x, y }: Vec2<T>) -> Self {
+        [x, y]
+    }
+}
+impl<T: Ord> Vec2<T> {
+    /// The smallest of each coordinate
+    pub fn min(self, other: Self) -> Self {
+        Self::new(self.x.min(other.x), self.y.min(other.y))
+    }
+    /// The largest of each coordinate
+    pub fn max(self, other: Self) -> Self {
+        Self::new(self.x.max(other.x), self.y.max(other.y))
+    }
+}
+impl<T: Neg<Output = T>> Vec2<T> {
+    /// A quarter turn clockwise, with y pointing down like [`ORTHOGONAL`]
+    pub fn rotate_cw(self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+    /// A quarter turn anticlockwise, with y pointing down like [`ORTHOGONAL`]
+    pub fn rotate_ccw(self) -> Self {
+        Self::new(self.y, -self.x)
+    }
+}
+
+/// Offsets to the four orthogonally adjacent positions, clockwise from the right (with y pointing down)
+pub const ORTHOGONAL: [Vec2<i8>; 4] = [Vec2::new(1, 0), Vec2::new(0, 1), Vec2::new(-1, 0), Vec2::new(0, -1)];
+/// Offsets to all eight surrounding positions, clockwise from the right
+pub const ADJACENT: [Vec2<i8>; 8] = [
+    Vec2::new(1, 0),
+    Vec2::new(1, 1),
+    Vec2::new(0, 1),
+    Vec2::new(-1, 1),
+    Vec2::new(-1, 0),
+    Vec2::new(-1, -1),
+    Vec2::new(0, -1),
+    Vec2::new(1, -1),
+];
+impl<T: Copy + Add<Output = T> + From<i8>> Vec2<T> {
+    pub fn orthogonal_neighbors(self) -> impl Iterator<Item = Self> {
+        ORTHOGONAL.into_iter().map(move |o| self + Vec2::new(o.x.into(), o.y.into()))
+    }
+    pub fn adjacent_neighbors(self) -> impl Iterator<Item = Self> {
+        ADJACENT.into_iter().map(move |o| self + Vec2::new(o.x.into(), o.y.into()))
+    }
+}
+impl Vec2<usize> {
+    pub fn checked_add_signed(self, offset: Vec2<i8>) -> Option<Self> {
+        Some(Vec2::new(
+            self.x.checked_add_signed(offset.x.into())?,
+            self.y.checked_add_signed(offset.y.into())?,
+        ))
+    }
+}
 impl<T: Add> Add for Vec2<T> {
     type Output = Vec2<T::Output>;
     fn add(self, rhs: Self) -> Self::Output {
@@ -49,6 +119,21 @@ impl<T: SubAssign> SubAssign for Vec2<T> {
         self.y -= rhs.y;
     }
 }
+impl<T: Mul + Copy> Mul<T> for Vec2<T> {
+    type Output = Vec2<T::Output>;
+    fn mul(self, rhs: T) -> Self::Output {
+        Vec2 {
+            x: self.x * rhs,
+            y: self.y * rhs,
+        }
+    }
+}
+impl<T: Neg> Neg for Vec2<T> {
+    type Output = Vec2<T::Output>;
+    fn neg(self) -> Self::Output {
+        Vec2 { x: -self.x, y: -self.y }
+    }
+}
 
 
 
@@ -64,6 +149,36 @@ impl<T> Vec3<T> {
         Self { x, y, z }
     }
 }
+impl<T> From<(T, T, T)> for Vec3<T> {
+    fn from((x, y, z): (T, T, T)) -> Self {
+        Self { x, y, z }
+    }
+}
+impl<T> From<Vec3<T>> for (T, T, T) {
+    fn from(Vec3 { x, y, z }: Vec3<T>) -> Self {
+        (x, y, z)
+    }
+}
+impl<T> From<[T; 3]> for Vec3<T> {
+    fn from([x, y, z]: [T; 3]) -> Self {
+        Self { x, y, z }
+    }
+}
+impl<T> From<Vec3<T>> for [T; 3] {
+    fn from(Vec3 { x, y, z }: Vec3<T>) -> Self {
+        [x, y, z]
+    }
+}
+impl<T: Ord> Vec3<T> {
+    /// The smallest of each coordinate
+    pub fn min(self, other: Self) -> Self {
+        Self::new(self.x.min(other.x), self.y.min(other.y), self.z.min(other.z))
+    }
+    /// The largest of each coordinate
+    pub fn max(self, other: Self) -> Self {
+        Self::new(self.x.max(other.x), self.y.max(other.y), self.z.max(other.z))
+    }
+}
 impl<T: Add> Add for Vec3<T> {
     type Output = Vec3<T::Output>;
     fn add(self, rhs: Self) -> Self::Output {
@@ -99,3 +214,107 @@ impl<T: SubAssign> SubAssign for Vec3<T> {
         self.z -= rhs.z;
     }
 }
+impl<T: Mul + Copy> Mul<T> for Vec3<T> {
+    type Output = Vec3<T::Output>;
+    fn mul(self, rhs: T) -> Self::Output {
+        Vec3 {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+impl<T: Neg> Neg for Vec3<T> {
+    type Output = Vec3<T::Output>;
+    fn neg(self) -> Self::Output {
+        Vec3 {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+}
+
+/// Distances for every integer type, measured in its unsigned counterpart so each coordinate's difference fits.
+/// The Manhattan distance adds those up, so is `None` if the sum doesn't fit
+macro_rules! impl_distances {
+    ($($int:ty => $unsigned:ty),*) => {$(
+        impl Vec2<$int> {
+            pub fn manhattan(self, other: Self) -> Option<$unsigned> {
+                self.x.abs_diff(other.x).checked_add(self.y.abs_diff(other.y))
+            }
+            /// The number of king moves between the two
+            pub fn chebyshev(self, other: Self) -> $unsigned {
+                self.x.abs_diff(other.x).max(self.y.abs_diff(other.y))
+            }
+        }
+        impl Vec3<$int> {
+            pub fn manhattan(self, other: Self) -> Option<$unsigned> {
+                self.x.abs_diff(other.x).checked_add(self.y.abs_diff(other.y))?.checked_add(self.z.abs_diff(other.z))
+            }
+            pub fn chebyshev(self, other: Self) -> $unsigned {
+                self.x.abs_diff(other.x).max(self.y.abs_diff(other.y)).max(self.z.abs_diff(other.z))
+            }
+        }
+    )*};
+}
+impl_distances!(
+    u8 => u8, u16 => u16, u32 => u32, u64 => u64, usize => usize,
+    i8 => u8, i16 => u16, i32 => u32, i64 => u64, isize => usize
+);
+
+/// Per-coordinate `signum` and `abs` for the signed integers
+macro_rules! impl_signed {
+    ($($int:ty),*) => {$(
+        impl Vec2<$int> {
+            /// A step of at most one in each direction towards `self`
+            pub fn signum(self) -> Self {
+                Self::new(self.x.signum(), self.y.signum())
+            }
+            pub fn abs(self) -> Self {
+                Self::new(self.x.abs(), self.y.abs())
+            }
+        }
+        impl Vec3<$int> {
+            pub fn signum(self) -> Self {
+                Self::new(self.x.signum(), self.y.signum(), self.z.signum())
+            }
+            pub fn abs(self) -> Self {
+                Self::new(self.x.abs(), self.y.abs(), self.z.abs())
+            }
+        }
+    )*};
+}
+impl_signed!(i8, i16, i32, i64, isize);
+
+#[cfg(test)]
+mod tests {
+    use super::{Vec2, Vec3, ORTHOGONAL};
+
+    #[test]
+    fn algebra() {
+        let v = Vec2::new(3i32, -4);
+        assert_eq!(v * 2, Vec2::new(6, -8));
+        assert_eq!(-v, Vec2::new(-3, 4));
+        assert_eq!(v.manhattan(Vec2::default()), Some(7));
+        assert_eq!(v.chebyshev(Vec2::new(-1, -1)), 4);
+        assert_eq!(v.signum(), Vec2::new(1, -1));
+        assert_eq!(v.abs(), Vec2::new(3, 4));
+        assert_eq!(v.min(Vec2::new(0, 0)), Vec2::new(0, -4));
+        assert_eq!(v.max(Vec2::new(0, 0)), Vec2::new(3, 0));
+        assert_eq!(Vec2::new(usize::MAX, 0).manhattan(Vec2::new(0, 0)), Some(usize::MAX));
+        assert_eq!(Vec2::<i8>::new(-128, -128).manhattan(Vec2::new(127, 127)), None);
+        assert_eq!(Vec2::<i8>::new(-128, 0).chebyshev(Vec2::new(127, 127)), 255);
+
+        for (i, o) in ORTHOGONAL.into_iter().enumerate() {
+            assert_eq!(o.rotate_cw(), ORTHOGONAL[(i + 1) % 4]);
+            assert_eq!(o.rotate_cw().rotate_ccw(), o);
+        }
+
+        let w = Vec3::from([1i8, -2, 3]);
+        assert_eq!(<(i8, i8, i8)>::from(-w * 2), (-2, 4, -6));
+        assert_eq!(w.manhattan(Vec3::new(0, 0, 0)), Some(6));
+        assert_eq!(Vec3::<u8>::new(200, 50, 6).manhattan(Vec3::new(0, 0, 0)), None);
+        assert_eq!(<[i8; 2]>::from(Vec2::from((1, 2))), [1, 2]);
+    }
+}